@@ -0,0 +1,171 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+use std::time::Instant;
+use crate::JsonGameState;
+use crate::board::GameBoard;
+use crate::cli::value;
+use crate::config::EngineConfig;
+use crate::eval::eval_breakdown;
+use crate::logic::{game_board, search_position, searchable};
+use crate::replay::{Decision, Replay};
+use crate::search::{format_pv, search_pool};
+use crate::time_manager::TimeManager;
+
+pub const USAGE: &str = "\
+Usage: analyze [FILE] [--turn N] [--depth N] [--time MS] [--threads N]
+
+Searches one position and prints what the engine makes of it. FILE (stdin if it's missing or `-`)
+holds either a /move request or a replay, in which case --turn picks the turn (the last one we
+moved on by default).
+
+  --turn N      turn of a replay to analyse
+  --depth N     deepest iteration to search, MAX_SEARCH_DEPTH by default
+  --time MS     time to search for, the request's timeout less MOVE_SAFETY_MARGIN_MS by default,
+                no limit if only --depth is given
+  --threads N   search threads, SEARCH_THREADS by default
+
+Every other setting is read from the environment the way the server reads it.";
+
+// What to analyse and how hard to search it
+#[derive(Debug, Default)]
+struct Options {
+    // A file name, stdin if missing
+    input: Option<String>,
+    turn: Option<i32>,
+    depth: Option<i32>,
+    time_ms: Option<u32>,
+    threads: Option<usize>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--turn" => options.turn = Some(value(&arg, args.next())?),
+                "--depth" => options.depth = Some(value::<i32>(&arg, args.next())?.max(1)),
+                "--time" => options.time_ms = Some(value(&arg, args.next())?),
+                "--threads" => options.threads = Some(value::<usize>(&arg, args.next())?.max(1)),
+                "-" if options.input.is_none() => options.input = None,
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ if options.input.is_none() => options.input = Some(arg),
+                _ => return Err(format!("more than one input given, {} is one too many", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+// Runs the `analyze` binary on its command line, without the program name
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let options = Options::parse(args).map_err(|e| format!("{}, see --help", e))?;
+
+    let (text, source) = match &options.input {
+        Some(path) => (fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?, path.clone()),
+        None => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            (text, "stdin".to_string())
+        }
+    };
+
+    // A /move request is one JSON object, a replay is one per line
+    let (request, board, played) = match serde_json::from_str::<JsonGameState>(&text) {
+        Ok(request) => {
+            searchable(&request.board)?;
+            let board = game_board(&request.game, &request.board);
+            (request, board, None)
+        }
+        Err(_) => {
+            let replay = Replay::parse(&text, &source)?;
+            let turn = match options.turn.or_else(|| replay.turns().last()) {
+                Some(turn) => turn,
+                None => return Err(format!("{} has no moves to analyse", source).into()),
+            };
+            let request = replay.request(turn)
+                .ok_or_else(|| format!("{} has no turn {}", source, turn))?
+                .clone();
+            searchable(&request.board)?;
+            let board = replay.board(turn).ok_or_else(|| format!("{} has no turn {}", source, turn))?;
+            (request, board, replay.decision(turn).cloned())
+        }
+    };
+
+    if !request.board.snakes.iter().any(|snake| snake.id == request.you.id) {
+        return Err(format!("{} isn't on the board on turn {}", request.you.name, request.turn).into());
+    }
+
+    analyze(&request, board, played.as_ref(), &options)
+}
+
+fn analyze(request: &JsonGameState, board: GameBoard, played: Option<&Decision>,
+           options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut config = EngineConfig::from_env();
+    if let Some(depth) = options.depth {
+        config.max_depth = depth;
+    }
+    if let Some(threads) = options.threads {
+        config.threads = threads;
+    }
+
+    // The time given is all the search gets, nothing is held back for the network
+    let timeout_ms = match (options.time_ms, options.depth) {
+        (Some(time_ms), _) => {
+            config.safety_margin_ms = 0;
+            time_ms
+        }
+        (None, Some(_)) => u32::MAX,
+        (None, None) => request.game.timeout,
+    };
+
+    let pool = search_pool(config.threads)?;
+
+    let us = board.snake_index(&request.you.id);
+    let mut players = vec![us];
+    players.extend((0..board.snakes.len()).filter(|i| *i != us));
+
+    let mut time_manager = TimeManager::new(Instant::now(), timeout_ms, 0, &config);
+    let (result, stats) = search_position(request, board.clone(), &mut time_manager, &pool, &config);
+
+    println!();
+    println!("Game {}, turn {}, {} to move", request.game.id, request.turn, request.you.name);
+    println!("Best move {} with score {} at depth {}", result.best_move.as_str(), result.score, result.depth);
+
+    println!();
+    println!("Root moves:");
+    for root_move in &result.root_moves {
+        let bound = if root_move.exact { "" } else { "<= " };
+        let best = if root_move.direction == result.best_move { "  (best)" } else { "" };
+        println!("  {:<6} {:>10}{}", root_move.direction.as_str(), format!("{}{}", bound, root_move.score), best);
+    }
+
+    println!();
+    println!("PV: {}", format_pv(&board, &result.pv));
+
+    if players.len() > 1 {
+        let breakdown = eval_breakdown(&board, &players);
+        println!();
+        println!("Eval of the position as it stands:");
+        println!("  territory {:>8}", breakdown.territory);
+        println!("  health    {:>8}", breakdown.health);
+        println!("  length    {:>8}", breakdown.length);
+        println!("  total     {:>8}", breakdown.total);
+    }
+
+    if let Some(played) = played {
+        println!();
+        match (played.score, played.depth) {
+            (Some(score), Some(depth)) => println!("Played in the game: {} with score {} at depth {}",
+                                                   played.direction, score, depth),
+            _ => println!("Played in the game: {} (fallback)", played.direction),
+        }
+    }
+
+    println!();
+    println!("Search stats: {}", serde_json::to_string_pretty(&stats.to_json())?);
+
+    Ok(())
+}
@@ -0,0 +1,641 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde_json::{json, Value};
+use crate::{Battlesnake, Board, Coord, Game, GameState, JsonGameState};
+use crate::board::{Direction, GameBoard, Ruleset};
+use crate::cli::value;
+use crate::config::EngineConfig;
+use crate::logic::{game_board, game_seed};
+use crate::replay::{self, Decision, ReplayEvent};
+use crate::search::{search_pool, think, SearchPool};
+use crate::stats::GameStats;
+use crate::time_manager::TimeManager;
+
+pub const USAGE: &str = "\
+Usage: arena [--games N] [--width N] [--height N] [--snakes N] [--mode MODE] [--time MS]
+             [--seed N] [--max-turns N] [--threads N] [--out DIR] [--openings FILE]
+             [--engine SPEC]...
+
+Plays engines against each other on this machine and prints how they did. Every game gets a
+replay per snake in DIR, next to a summary table.
+
+  --games N       games to play, 2 by default
+  --width N       board width, 11 by default
+  --height N      board height, 11 by default
+  --snakes N      snakes in every game, 2 by default
+  --mode MODE     standard, wrapped, constrictor, wrapped_constrictor or royale
+  --time MS       time every snake gets for a move, 100 by default
+  --seed N        where starting positions, food and hazards come from, random by default
+  --max-turns N   turns after which a game is a draw, 1000 by default
+  --threads N     search threads of every move, 1 by default
+  --out DIR       where replays and the summary go, `arena` by default
+  --openings FILE start from the positions in FILE instead: a /move request, one per line, or
+                  a replay, every turn of which is one. Width, height and snakes are theirs.
+  --engine SPEC   NAME or NAME:KEY=VALUE,KEY=VALUE..., an engine configured like the server
+                  with the settings given overriding the environment. Two engines `a` and `b`
+                  configured by the environment alone if none is given. Engines search with
+                  --seed unless their ENGINE_SEED is given here.
+
+Engines take the seats of a game in turn, and every starting position is played once with each
+engine on each seat before the next one comes up.";
+
+// Turns between two shrinks of the safe area in royale
+const ROYALE_SHRINK_EVERY: i32 = 25;
+
+// A contestant: a name for the tables and the settings it searches with
+#[derive(Debug, Clone)]
+pub struct Engine {
+    pub(crate) name: String,
+    pub(crate) config: EngineConfig,
+    // ENGINE_SEED was in the spec, the engine keeps it rather than taking the arena's --seed
+    pub(crate) own_seed: bool,
+}
+
+impl Engine {
+    // `NAME` or `NAME:KEY=VALUE,KEY=VALUE...`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, settings) = spec.split_once(':').unwrap_or((spec, ""));
+        if name.is_empty() {
+            return Err(format!("engine {:?} has no name", spec));
+        }
+
+        let overrides = settings.split(',')
+            .filter(|setting| !setting.is_empty())
+            .map(|setting| setting.split_once('=')
+                .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+                .ok_or_else(|| format!("setting {:?} of engine {} isn't KEY=VALUE", setting, name)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let config = EngineConfig::with_overrides(&overrides).map_err(|e| format!("engine {}: {}", name, e))?;
+        let own_seed = overrides.iter().any(|(key, _)| key == "ENGINE_SEED");
+        Ok(Self { name: name.to_string(), config, own_seed })
+    }
+}
+
+// The rules a game is played by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Standard,
+    Wrapped,
+    Constrictor,
+    WrappedConstrictor,
+    // Standard, with the board's edges turning into hazards a row or column at a time
+    Royale,
+}
+
+impl Mode {
+    // What the ruleset is called in a request
+    fn name(self) -> &'static str {
+        match self {
+            Mode::Standard => "standard",
+            Mode::Wrapped => "wrapped",
+            Mode::Constrictor => "constrictor",
+            Mode::WrappedConstrictor => "wrapped_constrictor",
+            Mode::Royale => "royale",
+        }
+    }
+
+    // `game.ruleset` of every request, with the settings the game server uses by default
+    fn ruleset(self) -> HashMap<String, Value> {
+        let ruleset = json!({
+            "name": self.name(),
+            "version": "arena",
+            "settings": {
+                "foodSpawnChance": 15,
+                "minimumFood": 1,
+                "hazardDamagePerTurn": if self == Mode::Royale { 14 } else { 0 },
+                "royale": { "shrinkEveryNTurns": ROYALE_SHRINK_EVERY },
+            },
+        });
+        serde_json::from_value(ruleset).unwrap()
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "standard" => Ok(Mode::Standard),
+            "wrapped" => Ok(Mode::Wrapped),
+            "constrictor" => Ok(Mode::Constrictor),
+            "wrapped_constrictor" => Ok(Mode::WrappedConstrictor),
+            "royale" => Ok(Mode::Royale),
+            _ => Err(format!("unknown game mode {}", s)),
+        }
+    }
+}
+
+// How every game of an arena is played
+#[derive(Debug, Clone)]
+pub struct ArenaSettings {
+    pub(crate) games: u64,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    pub(crate) snakes: usize,
+    pub(crate) mode: Mode,
+    pub(crate) time_ms: u32,
+    pub(crate) seed: u64,
+    pub(crate) max_turns: i32,
+    pub(crate) threads: usize,
+    pub(crate) out: PathBuf,
+    // Whether every game leaves a replay per snake in `out`
+    pub(crate) replays: bool,
+    // Where games start, generated from the seed if there are none
+    pub(crate) openings: Vec<Board>,
+}
+
+impl Default for ArenaSettings {
+    fn default() -> Self {
+        Self {
+            games: 2,
+            width: 11,
+            height: 11,
+            snakes: 2,
+            mode: Mode::Standard,
+            time_ms: 100,
+            seed: rand::random(),
+            max_turns: 1000,
+            threads: 1,
+            out: PathBuf::from("arena"),
+            replays: true,
+            openings: Vec::new(),
+        }
+    }
+}
+
+impl ArenaSettings {
+    // Takes `arg` and its value off the command line if it's one of the options games are played
+    // with, returns false if it isn't
+    pub fn parse_option(&mut self, arg: &str, args: &mut impl Iterator<Item = String>) -> Result<bool, String> {
+        match arg {
+            "--width" => self.width = value::<i32>(arg, args.next())?.max(1),
+            "--height" => self.height = value::<i32>(arg, args.next())?.max(1),
+            "--snakes" => self.snakes = value::<usize>(arg, args.next())?.max(1),
+            "--mode" => self.mode = value(arg, args.next())?,
+            "--time" => self.time_ms = value(arg, args.next())?,
+            "--seed" => self.seed = value(arg, args.next())?,
+            "--max-turns" => self.max_turns = value(arg, args.next())?,
+            "--threads" => self.threads = value::<usize>(arg, args.next())?.max(1),
+            "--out" => self.out = value(arg, args.next())?,
+            "--openings" => self.openings = load_openings(&value::<PathBuf>(arg, args.next())?)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    // Where and how the games are played, for reports
+    pub fn describe_games(&self) -> String {
+        if self.openings.is_empty() {
+            format!("on {}x{} {} with {} snakes", self.width, self.height, self.mode.name(), self.snakes)
+        } else {
+            format!("from {} openings, {}", self.openings.len(), self.mode.name())
+        }
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.openings.is_empty() {
+            GameBoard::check_size(self.width, self.height, self.snakes)?;
+            if self.snakes > (self.width * self.height) as usize / 2 {
+                return Err(format!("{} snakes don't fit on a {}x{} board", self.snakes, self.width, self.height));
+            }
+        }
+        for opening in &self.openings {
+            GameBoard::check_size(opening.width, opening.height, opening.snakes.len())?;
+        }
+        Ok(())
+    }
+}
+
+// How one game went
+#[derive(Debug, Clone)]
+pub struct GameOutcome {
+    pub(crate) game_id: String,
+    pub(crate) turns: i32,
+    // The engine of every snake, as an index into the arena's engines
+    pub(crate) seats: Vec<usize>,
+    // The snake that outlived the others, none if the last ones went out together or time ran out
+    pub(crate) winner: Option<usize>,
+    // Every search of the game, by engine
+    pub(crate) stats: Vec<GameStats>,
+}
+
+impl GameOutcome {
+    // The engine that won, if any
+    pub fn winning_engine(&self) -> Option<usize> {
+        self.winner.map(|snake| self.seats[snake])
+    }
+
+    // 1 for a win, 0.5 for a draw and 0 for a loss, none if `engine` didn't play
+    pub fn points(&self, engine: usize) -> Option<f64> {
+        if !self.seats.contains(&engine) {
+            return None;
+        }
+        Some(match self.winning_engine() {
+            Some(winner) if winner == engine => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        })
+    }
+}
+
+// What an engine did over a whole arena
+#[derive(Debug, Clone, Default)]
+struct Standing {
+    games: u32,
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    stats: GameStats,
+}
+
+// Runs the `arena` binary on its command line, without the program name
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let (settings, mut engines) = parse_args(args).map_err(|e| format!("{}, see --help", e))?;
+    if engines.is_empty() {
+        engines = vec![Engine::parse("a")?, Engine::parse("b")?];
+    }
+    for engine in &mut engines {
+        prepare(engine, &settings);
+    }
+
+    let pool = search_pool(settings.threads)?;
+
+    let mut standings = vec![Standing::default(); engines.len()];
+    let mut turns = 0;
+    for game in 0..settings.games {
+        let (start, seats, mut rng) = setup(&settings, engines.len(), game);
+        let game_id = format!("arena-{}-{}", settings.seed, game + 1);
+        let outcome = play_game(&settings, &engines, &game_id, &start, &seats, &mut rng, &pool)?;
+
+        println!("{}", describe(&outcome, &engines));
+        record(&mut standings, &outcome);
+        turns += outcome.turns as u64;
+    }
+
+    let summary = summary(&settings, &engines, &standings, turns);
+    println!();
+    print!("{}", summary);
+    fs::create_dir_all(&settings.out)?;
+    fs::write(settings.out.join("summary.txt"), summary)?;
+
+    Ok(())
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<(ArenaSettings, Vec<Engine>), String> {
+    let mut settings = ArenaSettings::default();
+    let mut engines = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => settings.games = value(&arg, args.next())?,
+            "--engine" => engines.push(Engine::parse(&value::<String>(&arg, args.next())?)?),
+            _ if settings.parse_option(&arg, &mut args)? => {}
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    settings.check()?;
+    Ok((settings, engines))
+}
+
+// Arena games are quiet, get all of the move time and search with the arena's threads, and with
+// its seed unless the engine was given one of its own
+pub fn prepare(engine: &mut Engine, settings: &ArenaSettings) {
+    engine.config.print_searches = false;
+    engine.config.safety_margin_ms = 0;
+    engine.config.threads = settings.threads;
+    if !engine.own_seed {
+        engine.config.seed = settings.seed;
+    }
+}
+
+// The starting position of game `game`, which engine plays which snake, and where the game's food
+// and hazards come from. Games come in rounds of one game per engine that share their starting
+// position, every game of a round moving the engines one seat on. Rounds go through the openings
+// in order if there are any.
+pub fn setup(settings: &ArenaSettings, engines: usize, game: u64) -> (Board, Vec<usize>, StdRng) {
+    let round = game / engines as u64;
+    let rotation = (game % engines as u64) as usize;
+
+    let mut rng = StdRng::seed_from_u64(settings.seed ^ (round + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    let start = if settings.openings.is_empty() {
+        start_position(settings, &mut rng)
+    } else {
+        settings.openings[(round % settings.openings.len() as u64) as usize].clone()
+    };
+    let seats = (0..start.snakes.len()).map(|snake| (snake + rotation) % engines).collect();
+
+    (start, seats, rng)
+}
+
+// Starting positions from a file: a /move request, or one per line, or a replay, whose every
+// request is one
+pub fn load_openings(path: &Path) -> Result<Vec<Board>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if let Ok(request) = serde_json::from_str::<JsonGameState>(&text) {
+        return Ok(vec![request.board]);
+    }
+
+    let mut openings = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = serde_json::from_str::<JsonGameState>(line)
+            .or_else(|_| serde_json::from_str::<ReplayEvent>(line).map(|event| event.request().clone()))
+            .map_err(|e| format!("{} line {}: {}", path.display(), i + 1, e))?;
+        if request.board.snakes.len() > 1 {
+            openings.push(request.board);
+        }
+    }
+
+    if openings.is_empty() {
+        return Err(format!("{} has no position with two snakes or more", path.display()));
+    }
+    Ok(openings)
+}
+
+// Snakes go on the corners and the middles of the edges, one in from the walls, the way the game
+// server places them. Boards too small for that and games with more than eight snakes get random
+// cells of the same colour of a checkerboard instead, so no two snakes start next to each other.
+fn start_position(settings: &ArenaSettings, rng: &mut StdRng) -> Board {
+    let (width, height) = (settings.width, settings.height);
+    let (right, top) = (width - 2, height - 2);
+    let center = Coord { x: (width - 1) / 2, y: (height - 1) / 2 };
+
+    let heads: Vec<Coord> = if width >= 7 && height >= 7 && settings.snakes <= 8 {
+        let mut corners = vec![Coord { x: 1, y: 1 }, Coord { x: 1, y: top }, Coord { x: right, y: 1 }, Coord { x: right, y: top }];
+        let mut edges = vec![Coord { x: 1, y: center.y }, Coord { x: center.x, y: 1 },
+                             Coord { x: right, y: center.y }, Coord { x: center.x, y: top }];
+        corners.shuffle(rng);
+        edges.shuffle(rng);
+        if rng.gen_bool(0.5) {
+            std::mem::swap(&mut corners, &mut edges);
+        }
+        corners.into_iter().chain(edges).take(settings.snakes).collect()
+    } else {
+        let mut cells: Vec<Coord> = (0..width)
+            .flat_map(|x| (0..height).map(move |y| Coord { x, y }))
+            .filter(|cell| (cell.x + cell.y) % 2 == 0)
+            .collect();
+        cells.shuffle(rng);
+        cells.truncate(settings.snakes);
+        cells
+    };
+
+    let snakes: Vec<Battlesnake> = heads.iter().enumerate()
+        .map(|(i, head)| Battlesnake {
+            id: format!("s{}", i + 1),
+            name: format!("s{}", i + 1),
+            health: 100,
+            body: vec![*head; 3],
+            head: *head,
+            length: 3,
+            latency: "0".to_string(),
+            shout: None,
+        })
+        .collect();
+
+    // A piece next to every snake, on a diagonal that isn't closer to the center, and one in the
+    // center. Constrictor has no food.
+    let mut food = Vec::new();
+    if !matches!(settings.mode, Mode::Constrictor | Mode::WrappedConstrictor) {
+        let distance = |cell: Coord| (cell.x - center.x).abs() + (cell.y - center.y).abs();
+        let free = |cell: &Coord, food: &[Coord]| cell.x >= 0 && cell.x < width && cell.y >= 0 && cell.y < height
+            && !heads.contains(cell) && !food.contains(cell) && *cell != center;
+
+        for head in &heads {
+            let diagonals: Vec<Coord> = [(-1, -1), (-1, 1), (1, -1), (1, 1)].iter()
+                .map(|(dx, dy)| Coord { x: head.x + dx, y: head.y + dy })
+                .filter(|cell| free(cell, &food) && distance(*cell) >= distance(*head))
+                .collect();
+            if let Some(cell) = diagonals.choose(rng) {
+                food.push(*cell);
+            }
+        }
+        if !heads.contains(&center) {
+            food.push(center);
+        }
+    }
+
+    Board { height, width, food, snakes, hazards: Vec::new() }
+}
+
+// The safe area of a royale game, everything outside of it is hazard
+struct SafeArea {
+    left: i32,
+    right: i32,
+    bottom: i32,
+    top: i32,
+}
+
+impl SafeArea {
+    // Turns one of its edges into hazard
+    fn shrink(&mut self, board: &mut GameBoard, rng: &mut StdRng) {
+        if self.left > self.right || self.bottom > self.top {
+            return;
+        }
+
+        let cells: Vec<Coord> = match rng.gen_range(0..4) {
+            0 => { self.left += 1; (self.bottom..=self.top).map(|y| Coord { x: self.left - 1, y }).collect() }
+            1 => { self.right -= 1; (self.bottom..=self.top).map(|y| Coord { x: self.right + 1, y }).collect() }
+            2 => { self.bottom += 1; (self.left..=self.right).map(|x| Coord { x, y: self.bottom - 1 }).collect() }
+            _ => { self.top -= 1; (self.left..=self.right).map(|x| Coord { x, y: self.top + 1 }).collect() }
+        };
+        for cell in cells {
+            board.add_hazard(cell);
+        }
+    }
+}
+
+// Plays a game out from `start`, snake `i` played by `engines[seats[i]]`. Every snake searches on
+// its own state the way the server would for a game of its own, and gets a replay in the arena's
+// output directory.
+#[allow(clippy::too_many_arguments)]
+pub fn play_game(settings: &ArenaSettings, engines: &[Engine], game_id: &str, start: &Board, seats: &[usize],
+                 rng: &mut StdRng, pool: &SearchPool) -> Result<GameOutcome, Box<dyn Error>> {
+    let game = Game { id: game_id.to_string(), ruleset: settings.mode.ruleset(), timeout: settings.time_ms };
+
+    let mut start = start.clone();
+    for (i, snake) in start.snakes.iter_mut().enumerate() {
+        snake.id = format!("s{}", i + 1);
+        snake.name = engines[seats[i]].name.clone();
+    }
+
+    let request = |board: &Board, turn: i32, you: Battlesnake| JsonGameState {
+        game: game.clone(), turn, board: board.clone(), you,
+    };
+    // Every snake has a replay of its own, named after the game and the snake
+    let replay_id = |snake: &Battlesnake| format!("{}-{}", game_id, snake.id);
+    let record = |event: ReplayEvent| -> Result<(), Box<dyn Error>> {
+        if settings.replays {
+            replay::append(&settings.out, &replay_id(&event.request().you), &event)?;
+        }
+        Ok(())
+    };
+
+    let mut players: Vec<GameState> = Vec::with_capacity(start.snakes.len());
+    for (i, snake) in start.snakes.iter().enumerate() {
+        let config = &engines[seats[i]].config;
+        let start_request = request(&start, 0, snake.clone());
+        let mut player = GameState::new(&start_request, config);
+        player.rng = StdRng::seed_from_u64(config.seed ^ game_seed(&replay_id(snake)));
+        players.push(player);
+        record(ReplayEvent::Start { request: start_request })?;
+    }
+
+    let mut board = GameBoard::new(start.width, start.height, start.food.clone(), start.snakes.clone(),
+                                   start.hazards.clone(), Ruleset::from_game(&game.ruleset));
+    let mut safe_area = SafeArea { left: 0, right: start.width - 1, bottom: 0, top: start.height - 1 };
+    let mut stats = vec![GameStats::default(); engines.len()];
+
+    let mut turn = 0;
+    loop {
+        let alive: Vec<usize> = (0..board.snakes.len()).filter(|snake| !board.is_eliminated(*snake)).collect();
+        let over = if board.snakes.len() > 1 { alive.len() <= 1 } else { alive.is_empty() };
+        if over || turn >= settings.max_turns {
+            break;
+        }
+
+        let api_board = board.to_board();
+        let mut moves = Vec::with_capacity(alive.len());
+        for &snake in &alive {
+            let engine = &engines[seats[snake]];
+            let you = board.to_battlesnake(snake);
+            let move_request = request(&api_board, turn, you);
+            let player = &mut players[snake];
+
+            let mut time_manager = TimeManager::new(Instant::now(), settings.time_ms, 0, &engine.config);
+            let (result, search_stats) = think(&mut player.game_recorder, game_board(&game, &api_board),
+                                               move_request.you.clone(), &player.tt, &mut player.killers,
+                                               &mut time_manager, pool, &mut player.rng, &engine.config);
+            player.stats.record(&search_stats);
+            stats[seats[snake]].record(&search_stats);
+
+            // Like the server, a legal move when the search has none
+            let direction = match result.best_move {
+                Direction::None => board.first_safe_move(snake).unwrap_or(Direction::Up),
+                best_move => best_move,
+            };
+            let decision = Decision::searched(direction.as_str(), &result, &api_board);
+            record(ReplayEvent::Move { request: move_request, decision })?;
+            moves.push((snake, direction));
+        }
+
+        board.step(&moves);
+        turn += 1;
+        if settings.mode == Mode::Royale && turn % ROYALE_SHRINK_EVERY == 0 {
+            safe_area.shrink(&mut board, rng);
+        }
+        board.spawn_food(rng);
+    }
+
+    let alive: Vec<usize> = (0..board.snakes.len()).filter(|snake| !board.is_eliminated(*snake)).collect();
+    // A game still going at max turns is a draw, one that was won on its last turn is not
+    let winner = match alive.as_slice() {
+        [winner] if board.snakes.len() > 1 => Some(*winner),
+        _ => None,
+    };
+
+    let end_board = board.to_board();
+    for snake in 0..board.snakes.len() {
+        let you = board.to_battlesnake(snake);
+        record(ReplayEvent::End { request: request(&end_board, turn, you) })?;
+    }
+
+    Ok(GameOutcome { game_id: game_id.to_string(), turns: turn, seats: seats.to_vec(), winner, stats })
+}
+
+// One line about how a game went
+pub fn describe(outcome: &GameOutcome, engines: &[Engine]) -> String {
+    let snakes: Vec<String> = outcome.seats.iter().enumerate()
+        .map(|(i, engine)| format!("s{} {}", i + 1, engines[*engine].name))
+        .collect();
+    let result = match outcome.winner {
+        Some(snake) => format!("s{} {} won", snake + 1, engines[outcome.seats[snake]].name),
+        None => "draw".to_string(),
+    };
+
+    format!("{} ({}): {} after {} turns", outcome.game_id, snakes.join(", "), result, outcome.turns)
+}
+
+fn record(standings: &mut [Standing], outcome: &GameOutcome) {
+    for (engine, standing) in standings.iter_mut().enumerate() {
+        let points = match outcome.points(engine) {
+            Some(points) => points,
+            None => continue,
+        };
+
+        standing.games += 1;
+        if points == 1.0 {
+            standing.wins += 1;
+        } else if points == 0.0 {
+            standing.losses += 1;
+        } else {
+            standing.draws += 1;
+        }
+        standing.stats.add(&outcome.stats[engine]);
+    }
+}
+
+fn summary(settings: &ArenaSettings, engines: &[Engine], standings: &[Standing], turns: u64) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "{} games {}, {}ms a move, seed {}, {:.1} turns a game",
+             settings.games, settings.describe_games(), settings.time_ms, settings.seed,
+             turns as f64 / settings.games.max(1) as f64).unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "{:<16} {:>6} {:>6} {:>6} {:>6} {:>7} {:>7} {:>10}",
+             "Engine", "Games", "Wins", "Draws", "Losses", "Score", "Depth", "NPS").unwrap();
+
+    for (engine, standing) in engines.iter().zip(standings) {
+        let score = if standing.games > 0 {
+            (standing.wins as f64 + standing.draws as f64 / 2.0) / standing.games as f64 * 100.0
+        } else {
+            0.0
+        };
+        writeln!(out, "{:<16} {:>6} {:>6} {:>6} {:>6} {:>6.1}% {:>7.1} {:>10}",
+                 engine.name, standing.games, standing.wins, standing.draws, standing.losses, score,
+                 standing.stats.average_depth(), standing.stats.nps()).unwrap();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::search_pool;
+
+    #[test]
+    fn a_tiny_game_is_played_to_the_end() {
+        let settings = ArenaSettings {
+            width: 7, height: 7, time_ms: 20, seed: 7, max_turns: 30, replays: false,
+            ..ArenaSettings::default()
+        };
+        let mut engines = vec![Engine::parse("a:MAX_SEARCH_DEPTH=3").unwrap(), Engine::parse("b:MAX_SEARCH_DEPTH=3").unwrap()];
+        for engine in &mut engines {
+            prepare(engine, &settings);
+        }
+
+        let (start, seats, mut rng) = setup(&settings, engines.len(), 0);
+        let pool = search_pool(settings.threads).unwrap();
+        let outcome = play_game(&settings, &engines, "smoke", &start, &seats, &mut rng, &pool).unwrap();
+
+        assert!(outcome.turns > 0 && outcome.turns <= settings.max_turns);
+        assert_eq!(outcome.seats, vec![0, 1]);
+        // The game is over as soon as a snake is out, so both searched on every turn
+        for stats in &outcome.stats {
+            assert_eq!(stats.moves as i32, outcome.turns);
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use std::env;
+use std::process;
+use starter_snake_rust::analyze;
+
+fn main() {
+    env_logger::init();
+
+    if env::args().skip(1).any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", analyze::USAGE);
+        return;
+    }
+
+    if let Err(e) = analyze::run(env::args().skip(1)) {
+        eprintln!("analyze: {}", e);
+        process::exit(1);
+    }
+}
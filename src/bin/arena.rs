@@ -0,0 +1,17 @@
+use std::env;
+use std::process;
+use starter_snake_rust::arena;
+
+fn main() {
+    env_logger::init();
+
+    if env::args().skip(1).any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", arena::USAGE);
+        return;
+    }
+
+    if let Err(e) = arena::run(env::args().skip(1)) {
+        eprintln!("arena: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,17 @@
+use std::env;
+use std::process;
+use starter_snake_rust::sprt;
+
+fn main() {
+    env_logger::init();
+
+    if env::args().skip(1).any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", sprt::USAGE);
+        return;
+    }
+
+    if let Err(e) = sprt::run(env::args().skip(1)) {
+        eprintln!("sprt: {}", e);
+        process::exit(1);
+    }
+}
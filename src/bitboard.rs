@@ -0,0 +1,357 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+use crate::Coord;
+
+const WORDS: usize = 16;
+
+// Boards up to this many cells fit in a `Bitboard`, 32x32 and past anything the game server makes
+pub const MAX_CELLS: usize = WORDS * 64;
+
+// One bit per cell, cell (x, y) is bit y * width + x. Bits past the board's last cell are kept
+// clear by everything that comes out of a `Layout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Bitboard([u64; WORDS]);
+
+impl Bitboard {
+    pub fn single(index: usize) -> Self {
+        let mut board = Self::default();
+        board.set(index);
+        board
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn set(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn clear(&mut self, index: usize) {
+        self.0[index / 64] &= !(1 << (index % 64));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    pub fn count_ones(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    // Indices of the set bits, lowest first
+    pub fn ones(self) -> impl Iterator<Item = usize> {
+        IntoIterator::into_iter(self.0).enumerate().flat_map(|(i, word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(mut self, rhs: Self) -> Self {
+        self &= rhs;
+        self
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        for (word, other) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *word &= other;
+        }
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+
+    fn bitor(mut self, rhs: Self) -> Self {
+        self |= rhs;
+        self
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        for (word, other) in self.0.iter_mut().zip(rhs.0.iter()) {
+            *word |= other;
+        }
+    }
+}
+
+// Also sets every bit past the board, mask with `Layout::all` when that matters
+impl Not for Bitboard {
+    type Output = Self;
+
+    fn not(mut self) -> Self {
+        for word in self.0.iter_mut() {
+            *word = !*word;
+        }
+        self
+    }
+}
+
+// What flood fills run on. A `u128` holds the standard board and is a lot cheaper to push
+// around than a full `Bitboard`, which is only needed past 128 cells.
+pub trait Bits: Copy + Default + BitAnd<Output = Self> + BitAndAssign + BitOr<Output = Self> + BitOrAssign + Not<Output = Self> {
+    fn from_bitboard(board: Bitboard) -> Self;
+    fn single(index: usize) -> Self;
+    fn is_empty(&self) -> bool;
+    fn count_ones(&self) -> u32;
+    // Towards higher indices, only the first `words` words can hold anything
+    fn shift_up(self, by: usize, words: usize) -> Self;
+    // Towards lower indices
+    fn shift_down(self, by: usize, words: usize) -> Self;
+}
+
+impl Bits for Bitboard {
+    fn from_bitboard(board: Bitboard) -> Self {
+        board
+    }
+
+    fn single(index: usize) -> Self {
+        Bitboard::single(index)
+    }
+
+    fn is_empty(&self) -> bool {
+        Bitboard::is_empty(self)
+    }
+
+    fn count_ones(&self) -> u32 {
+        Bitboard::count_ones(self)
+    }
+
+    fn shift_up(self, by: usize, words: usize) -> Self {
+        let (word_shift, bit_shift) = (by / 64, by % 64);
+        let mut result = Bitboard::default();
+
+        for i in word_shift..words {
+            let mut word = self.0[i - word_shift] << bit_shift;
+            if bit_shift > 0 && i > word_shift {
+                word |= self.0[i - word_shift - 1] >> (64 - bit_shift);
+            }
+            result.0[i] = word;
+        }
+
+        result
+    }
+
+    fn shift_down(self, by: usize, words: usize) -> Self {
+        let (word_shift, bit_shift) = (by / 64, by % 64);
+        let mut result = Bitboard::default();
+
+        for i in 0..words.saturating_sub(word_shift) {
+            let mut word = self.0[i + word_shift] >> bit_shift;
+            if bit_shift > 0 && i + word_shift + 1 < words {
+                word |= self.0[i + word_shift + 1] << (64 - bit_shift);
+            }
+            result.0[i] = word;
+        }
+
+        result
+    }
+}
+
+impl Bits for u128 {
+    fn from_bitboard(board: Bitboard) -> Self {
+        board.0[0] as u128 | (board.0[1] as u128) << 64
+    }
+
+    fn single(index: usize) -> Self {
+        1 << index
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == 0
+    }
+
+    fn count_ones(&self) -> u32 {
+        u128::count_ones(*self)
+    }
+
+    fn shift_up(self, by: usize, _words: usize) -> Self {
+        self.checked_shl(by as u32).unwrap_or(0)
+    }
+
+    fn shift_down(self, by: usize, _words: usize) -> Self {
+        self.checked_shr(by as u32).unwrap_or(0)
+    }
+}
+
+// How cells of one board size map onto bits, and the masks for moving a whole board of bits a
+// step in some direction at once
+#[derive(Debug, Clone)]
+pub struct Layout<B: Bits = Bitboard> {
+    width: usize,
+    height: usize,
+    wrapped: bool,
+    // Words that hold any cell, shifts don't need to look past them
+    words: usize,
+    all: B,
+    first_column: B,
+    last_column: B,
+    first_row: B,
+    last_row: B,
+}
+
+impl Layout {
+    pub fn new(width: usize, height: usize, wrapped: bool) -> Self {
+        assert!(width * height <= MAX_CELLS, "a {}x{} board doesn't fit in a bitboard", width, height);
+
+        let mut layout = Self {
+            width,
+            height,
+            wrapped,
+            words: (width * height).div_ceil(64),
+            all: Bitboard::default(),
+            first_column: Bitboard::default(),
+            last_column: Bitboard::default(),
+            first_row: Bitboard::default(),
+            last_row: Bitboard::default(),
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                layout.all.set(index);
+                if x == 0 {
+                    layout.first_column.set(index);
+                }
+                if x == width - 1 {
+                    layout.last_column.set(index);
+                }
+                if y == 0 {
+                    layout.first_row.set(index);
+                }
+                if y == height - 1 {
+                    layout.last_row.set(index);
+                }
+            }
+        }
+
+        layout
+    }
+
+    // Whether the board fits in a `u128`
+    pub fn fits_u128(&self) -> bool {
+        self.width * self.height <= 128
+    }
+
+    // The same layout for another kind of bits, the board has to fit in them
+    pub fn with_bits<C: Bits>(&self) -> Layout<C> {
+        Layout {
+            width: self.width,
+            height: self.height,
+            wrapped: self.wrapped,
+            words: self.words,
+            all: C::from_bitboard(self.all),
+            first_column: C::from_bitboard(self.first_column),
+            last_column: C::from_bitboard(self.last_column),
+            first_row: C::from_bitboard(self.first_row),
+            last_row: C::from_bitboard(self.last_row),
+        }
+    }
+}
+
+impl<B: Bits> Layout<B> {
+    // `coord` has to be on the board
+    pub fn index(&self, coord: Coord) -> usize {
+        coord.y as usize * self.width + coord.x as usize
+    }
+
+    pub fn coord(&self, index: usize) -> Coord {
+        Coord { x: (index % self.width) as i32, y: (index / self.width) as i32 }
+    }
+
+    pub fn all(&self) -> B {
+        self.all
+    }
+
+    // Every cell one step away from a cell in `board`, around the edges in wrapped games
+    pub fn neighbours(&self, board: B) -> B {
+        let mut result = (board & !self.last_column).shift_up(1, self.words)
+            | (board & !self.first_column).shift_down(1, self.words)
+            | board.shift_up(self.width, self.words)
+            | board.shift_down(self.width, self.words);
+
+        if self.wrapped {
+            result |= (board & self.last_column).shift_down(self.width - 1, self.words)
+                | (board & self.first_column).shift_up(self.width - 1, self.words)
+                | (board & self.last_row).shift_down((self.height - 1) * self.width, self.words)
+                | (board & self.first_row).shift_up((self.height - 1) * self.width, self.words);
+        }
+
+        result & self.all
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Neighbours of every single cell, worked out one coordinate at a time
+    fn check_neighbours<B: Bits + PartialEq + std::fmt::Debug>(width: usize, height: usize, wrapped: bool) {
+        let layout: Layout<B> = Layout::new(width, height, wrapped).with_bits();
+
+        for index in 0..width * height {
+            let cell = layout.coord(index);
+            let mut expected = B::default();
+            for (dx, dy) in [(0, 1), (0, -1), (1, 0), (-1, 0)].iter() {
+                let (mut x, mut y) = (cell.x + dx, cell.y + dy);
+                if wrapped {
+                    x = x.rem_euclid(width as i32);
+                    y = y.rem_euclid(height as i32);
+                }
+                if x >= 0 && x < width as i32 && y >= 0 && y < height as i32 {
+                    expected |= B::single(layout.index(Coord { x, y }));
+                }
+            }
+
+            assert_eq!(layout.neighbours(B::single(index)), expected,
+                       "cell {:?} of a {}x{} board, wrapped: {}", cell, width, height, wrapped);
+        }
+    }
+
+    #[test]
+    fn neighbours_stop_at_edges() {
+        check_neighbours::<u128>(11, 11, false);
+        check_neighbours::<Bitboard>(11, 11, false);
+        check_neighbours::<Bitboard>(19, 19, false);
+        check_neighbours::<Bitboard>(7, 3, false);
+    }
+
+    #[test]
+    fn neighbours_wrap_around_edges() {
+        check_neighbours::<u128>(11, 11, true);
+        check_neighbours::<Bitboard>(11, 11, true);
+        check_neighbours::<Bitboard>(19, 19, true);
+        check_neighbours::<Bitboard>(7, 3, true);
+    }
+
+    #[test]
+    fn shifts_carry_across_words() {
+        assert_eq!(Bitboard::single(63).shift_up(1, 2), Bitboard::single(64));
+        assert_eq!(Bitboard::single(64).shift_down(1, 2), Bitboard::single(63));
+        assert_eq!(Bitboard::single(5).shift_up(130, 3), Bitboard::single(135));
+        assert_eq!(Bitboard::single(135).shift_down(130, 3), Bitboard::single(5));
+        assert_eq!(u128::single(63).shift_up(1, 2), u128::single(64));
+        assert_eq!(u128::single(64).shift_down(1, 2), u128::single(63));
+    }
+
+    #[test]
+    fn shifts_drop_bits_past_the_ends() {
+        assert!(Bitboard::single(127).shift_up(1, 2).is_empty());
+        assert!(Bitboard::single(3).shift_down(4, 2).is_empty());
+        assert!(u128::single(127).shift_up(1, 2).is_empty());
+        assert!(u128::single(3).shift_down(4, 2).is_empty());
+    }
+}
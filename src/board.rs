@@ -1,14 +1,14 @@
-use std::cell::RefCell;
-use std::rc::Rc;
-use crate::{Battlesnake, Coord};
+use std::collections::HashMap;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde_json::Value;
+use crate::{Battlesnake, Board, Coord};
+use crate::bitboard::{Bitboard, Layout, MAX_CELLS};
+use crate::snake::Snake;
+use crate::zobrist::{self, Segment};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum CellContent {
-    Food,
-    Snake(String),
-    Hazard,
-    Empty,
-}
+// Step records keep track of snakes in one bit each
+pub const MAX_SNAKES: usize = 64;
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum Direction {
@@ -19,4455 +19,5165 @@ pub enum Direction {
     None,
 }
 
+impl Direction {
+    // The way the API spells it
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::None => "none",
+        }
+    }
+}
+
+impl Coord {
+    pub fn step(self, direction: Direction) -> Coord {
+        match direction {
+            Direction::Up => Coord { x: self.x, y: self.y + 1 },
+            Direction::Down => Coord { x: self.x, y: self.y - 1 },
+            Direction::Left => Coord { x: self.x - 1, y: self.y },
+            Direction::Right => Coord { x: self.x + 1, y: self.y },
+            Direction::None => self,
+        }
+    }
+}
+
+// Why a snake left the game, named after the causes in the reference rules
+// See https://docs.battlesnake.com/guides/game/rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EliminationCause {
+    OutOfHealth,
+    OutOfBounds,
+    SelfCollision,
+    Collision,
+    HeadToHeadCollision,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elimination {
+    pub(crate) snake: usize,
+    pub(crate) cause: EliminationCause,
+    // Whoever was run into, for collisions
+    pub(crate) by: Option<usize>,
+}
+
+// Rule variants that change how snakes move, from the ruleset's name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Standard,
+    // Leaving the board over an edge comes back in on the opposite edge
+    Wrapped,
+    // Every snake grows every turn and never goes hungry, so tails never move
+    Constrictor,
+    WrappedConstrictor,
+}
+
+impl GameMode {
+    pub fn wraps(self) -> bool {
+        matches!(self, GameMode::Wrapped | GameMode::WrappedConstrictor)
+    }
+
+    pub fn constricts(self) -> bool {
+        matches!(self, GameMode::Constrictor | GameMode::WrappedConstrictor)
+    }
+}
+
+// The parts of the game's ruleset the board needs to play a turn
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ruleset {
+    pub(crate) mode: GameMode,
+    pub(crate) food_spawn_chance: i32,
+    pub(crate) minimum_food: i32,
+    // Extra health lost for ending a turn in a hazard without eating
+    pub(crate) hazard_damage_per_turn: i32,
+}
+
+impl Default for Ruleset {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::Standard,
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+        }
+    }
+}
+
+impl Ruleset {
+    // `ruleset` is the `game.ruleset` object of a request, anything missing keeps its default
+    pub fn from_game(ruleset: &HashMap<String, Value>) -> Self {
+        let mode = match ruleset.get("name").and_then(Value::as_str) {
+            Some("wrapped") => GameMode::Wrapped,
+            Some("constrictor") => GameMode::Constrictor,
+            Some("wrapped_constrictor") => GameMode::WrappedConstrictor,
+            _ => GameMode::Standard,
+        };
+        let mut parsed = Self { mode, ..Self::default() };
+
+        let settings = match ruleset.get("settings") {
+            Some(settings) => settings,
+            None => return parsed,
+        };
+
+        if let Some(value) = settings.get("foodSpawnChance").and_then(Value::as_i64) {
+            parsed.food_spawn_chance = value as i32;
+        }
+        if let Some(value) = settings.get("minimumFood").and_then(Value::as_i64) {
+            parsed.minimum_food = value as i32;
+        }
+        if let Some(value) = settings.get("hazardDamagePerTurn").and_then(Value::as_i64) {
+            parsed.hazard_damage_per_turn = value as i32;
+        }
+
+        parsed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Action {
-    pub(crate) snake_id: String,
+    // Index into `GameBoard::snakes`
+    pub(crate) snake: usize,
     pub(crate) direction: Direction,
     pub(crate) old_tail: Coord,
     // pub(crate) secondLastTail: Coord,
     pub(crate) new_head: Coord,
     pub(crate) old_head: Coord,
     pub(crate) ate_food: bool,
+    // Ate, or plays constrictor where everybody grows every turn
+    pub(crate) grew: bool,
     pub(crate) old_health: i32,
+    // Several heads can land on the same food, only the first one to arrive takes it off the board
+    pub(crate) took_food: bool,
+    // The snake's own body was already on the new head's cell, only after a self-collision
+    pub(crate) head_landed_on_body: bool,
+    // The old tail's cell was left, it stays covered when the tail was stacked
+    pub(crate) tail_cleared: bool,
+}
+
+// Everything `undo_step` needs to put back after eliminations were applied, snakes are one bit each
+#[derive(Debug, Clone)]
+struct StepRecord {
+    movers: u64,
+    eliminated: u64,
+    zobrist_hash: u64,
 }
 
 #[derive(Debug, Clone)]
 pub struct GameBoard {
     pub(crate) width: i32,
     pub(crate) height: i32,
-    pub(crate) matrix: Vec<Vec<CellContent>>,
-    pub(crate) snakes: Vec<Rc<RefCell<Battlesnake>>>,
+    pub(crate) layout: Layout,
+    pub(crate) food: Bitboard,
+    pub(crate) hazards: Bitboard,
+    pub(crate) snakes: Vec<Snake>,
+    // Parallel to `snakes`, what the API knows them by
+    pub(crate) ids: Vec<String>,
+    pub(crate) names: Vec<String>,
+    // Parallel to `snakes`, every cell each body covers. Eliminated snakes keep theirs, they are
+    // just left out whenever the board is looked at.
+    pub(crate) bodies: Vec<Bitboard>,
     pub(crate) history: Vec<Action>,
     pub(crate) zobrist_hash: u64,
-    pub(crate) health_zobrist_table: Vec<u64>,
-    pub(crate) zobrist_table: Vec<u64>,
-    pub(crate) boolboard: [[bool; 11]; 11],
-    pub(crate) headboard: [[i32; 11]; 11],
-    pub(crate) board_hash: u128,
-    move_map: [Vec<Direction>; 4096],
+    // Whose turn it is when snakes take turns, part of the hash so the same bodies with a
+    // different snake to move are a different position
+    pub(crate) to_move: usize,
+    pub(crate) ruleset: Ruleset,
+    // Parallel to `snakes`, eliminated snakes stay in there but are taken off the board
+    pub(crate) eliminated: Vec<Option<EliminationCause>>,
+    steps: Vec<StepRecord>,
 }
 
 impl GameBoard {
+    // Whether a board this size with this many snakes can be searched at all: bitboards hold
+    // `MAX_CELLS` cells, a 32x32 board, and step records a bit per snake. `new` panics otherwise.
+    pub fn check_size(width: i32, height: i32, snakes: usize) -> Result<(), String> {
+        if width < 1 || height < 1 || (width * height) as usize > MAX_CELLS {
+            return Err(format!("a {}x{} board is too big, boards can have at most {} cells", width, height, MAX_CELLS));
+        }
+        if snakes > MAX_SNAKES {
+            return Err(format!("{} snakes are too many, boards can have at most {}", snakes, MAX_SNAKES));
+        }
+        Ok(())
+    }
+
     pub fn new(width: i32, height: i32, food: Vec<Coord>, snakes: Vec<Battlesnake>, hazards: Vec<Coord>,
-               zobrist_table: &Vec<u64>, health_zobrist_table: &Vec<u64>) -> Self {
-        let mut matrix = vec![vec![CellContent::Empty; height as usize]; width as usize];
+               ruleset: Ruleset) -> Self {
+        assert!(snakes.len() <= MAX_SNAKES, "{} snakes is more than a board can hold", snakes.len());
+        let layout = Layout::new(width as usize, height as usize, ruleset.mode.wraps());
 
-        // let mut zobrist_table = Vec::new();
-        // let mut health_zobrist_table = Vec::new();
-        // let mut rng = rand::thread_rng();
-        // for _ in 0..(width * height * 2) {
-        //     zobrist_table.push(rng.gen());
-        // }
-        // for _ in 0..100 {
-        //     health_zobrist_table.push(rng.gen());
-        // }
+        let mut food_board = Bitboard::default();
+        for coord in &food {
+            food_board.set(layout.index(*coord));
+        }
 
-        let mut zobrist_hash: u64 = 0;
-        let mut boolboard: [[bool; 11]; 11] = [[false; 11]; 11];
-        let mut headboard: [[i32; 11]; 11] = [[0; 11]; 11];
+        // A body can't cover more than every cell plus the stacked segments at its tail
+        let body_capacity = (width * height) as usize + 8;
 
-        let mut board_hash: u128 = 0;
+        let mut board_snakes = Vec::with_capacity(snakes.len());
+        let mut bodies = Vec::with_capacity(snakes.len());
+        for (i, snake) in snakes.iter().enumerate() {
+            let mut body = Bitboard::default();
+            for coord in &snake.body {
+                body.set(layout.index(*coord));
+            }
 
-        for coord in &food {
-            matrix[coord.x as usize][coord.y as usize] = CellContent::Food;
-            // Food hashes
-            zobrist_hash ^= zobrist_table[((coord.x * height * 2) + (coord.y * 2) + 0) as usize];
-            board_hash ^= 1 << (coord.x * height + coord.y);
+            board_snakes.push(Snake::from_battlesnake(i, snake, body_capacity));
+            bodies.push(body);
         }
 
-        let mut ref_snakes = Vec::new();
-        for snake in snakes {
-            zobrist_hash ^= health_zobrist_table[(snake.health - 1) as usize];
-            let ref_snake = Rc::new(RefCell::new(snake.clone()));
-            ref_snakes.push(ref_snake.clone());
+        let mut hazard_board = Bitboard::default();
+        for coord in hazards {
+            hazard_board.set(layout.index(coord));
+        }
 
-            let mut i = 0;
-            for coord in &snake.body {
-                matrix[coord.x as usize][coord.y as usize] = CellContent::Snake(snake.id.clone());
+        let eliminated = vec![None; board_snakes.len()];
 
-                // Snake hashes
-                zobrist_hash ^= zobrist_table[((coord.x * height * 2) + (coord.y * 2) + 1) as usize];
-                board_hash ^= 1 << (coord.x * height + coord.y);
+        let mut board = Self {
+            width,
+            height,
+            layout,
+            food: food_board,
+            hazards: hazard_board,
+            snakes: board_snakes,
+            ids: snakes.iter().map(|snake| snake.id.clone()).collect(),
+            names: snakes.iter().map(|snake| snake.name.clone()).collect(),
+            bodies,
+            history: Vec::new(), // Initialize history
+            zobrist_hash: 0,
+            to_move: 0,
+            ruleset,
+            eliminated,
+            steps: Vec::new(),
+        };
+        board.zobrist_hash = board.compute_hash();
 
-                // Passability boolboard
-                if i < snake.length - 1 {
-                    boolboard[coord.x as usize][coord.y as usize] = true;
-                }
-                if i == 0 {
-                    headboard[coord.x as usize][coord.y as usize] = snake.length.clone();
-                }
+        board
+    }
 
+    // The hash of the whole position worked out from scratch, `zobrist_hash` is kept equal to
+    // this one move at a time
+    pub fn compute_hash(&self) -> u64 {
+        let mut hash = 0;
 
-                i += 1;
+        for index in self.food.ones() {
+            hash ^= zobrist::food(index);
+        }
+        for index in self.hazards.ones() {
+            hash ^= zobrist::hazard(index);
+        }
+        for snake in 0..self.snakes.len() {
+            if self.eliminated[snake].is_none() {
+                hash ^= self.snake_hash(snake);
             }
         }
+        hash ^= zobrist::turn(self.to_move);
 
-        for coord in hazards {
-            matrix[coord.x as usize][coord.y as usize] = CellContent::Hazard;
+        hash
+    }
+
+    // Checks the incremental hash against `compute_hash` after every change to the board when
+    // built with the `verify-hash` feature
+    fn verify_hash(&self) {
+        if cfg!(feature = "verify-hash") {
+            assert_eq!(self.zobrist_hash, self.compute_hash(), "incremental hash is out of sync");
         }
+    }
 
+    // Key of one segment of `snake`, heads that went off the board don't have one
+    fn segment_key(&self, snake: usize, coord: Coord, segment: Segment) -> u64 {
+        if self.in_bounds(coord) {
+            zobrist::segment(snake, self.layout.index(coord), segment)
+        } else {
+            0
+        }
+    }
 
-        Self {
-            width,
-            height,
-            matrix,
-            snakes: ref_snakes,
-            history: Vec::new(), // Initialize history
-            zobrist_hash: zobrist_hash,
-            health_zobrist_table: health_zobrist_table.clone(),
-            zobrist_table: zobrist_table.clone(),
-            boolboard: boolboard,
-            headboard: headboard,
-            board_hash: board_hash,
-            // First 4 bits for obstacles that are UP, LEFT, DOWN, RIGHT (in that order)
-            // Next 8 bits are for snake heads that are of dist 2 away (Manhattan)
-            // Order is:
-            // UP-UP, UP-LEFT, LEFT-LEFT, DOWN-LEFT, DOWN-DOWN, DOWN-RIGHT, RIGHT-RIGHT, UP-RIGHT
-            move_map: [
-                vec![Direction::Up, Direction::Left, Direction::Down, Direction::Right],
-                vec![Direction::Left, Direction::Down, Direction::Right],
-                vec![Direction::Up, Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Up, Direction::Left, Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left, Direction::Down, Direction::Right],
-                vec![Direction::Left, Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Up, Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Down, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Left, Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Left, Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Left, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up, Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![Direction::Right],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up, Direction::Left],
-                vec![Direction::Left],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![Direction::Up],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Left, Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![Direction::Down],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![Direction::Left],
-                vec![Direction::Left],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-                vec![],
-            ],
+    // Everything about `snake` that changes whenever it moves: which cells are its head and its
+    // tail, its health and its length
+    fn snake_ends_hash(&self, snake: usize) -> u64 {
+        let body = &self.snakes[snake];
+
+        self.segment_key(snake, body.head(), Segment::Head)
+            ^ self.segment_key(snake, body.tail(), Segment::Tail)
+            ^ zobrist::health(snake, body.health)
+            ^ zobrist::length(snake, body.length)
+    }
+
+    // Everything `snake` adds to the hash
+    fn snake_hash(&self, snake: usize) -> u64 {
+        self.snakes[snake].body.iter()
+            .fold(self.snake_ends_hash(snake), |hash, coord| hash ^ self.segment_key(snake, coord, Segment::Body))
+    }
+
+    // The snake at `index` as the API describes it
+    pub fn to_battlesnake(&self, index: usize) -> Battlesnake {
+        let snake = &self.snakes[index];
+
+        Battlesnake {
+            id: self.ids[index].clone(),
+            name: self.names[index].clone(),
+            health: snake.health,
+            body: snake.body.iter().collect(),
+            head: snake.head(),
+            length: snake.length,
+            latency: String::new(),
+            shout: None,
         }
     }
 
-    pub fn move_snake(&mut self, snake: Rc<RefCell<Battlesnake>>, direction: Direction) -> bool {
-        let start_time = std::time::Instant::now();
-        let mut borrow = snake.borrow_mut();
-
-        let mut action: Action = Action {
-            snake_id: borrow.id.clone(),
-            direction: direction.clone(),
-            old_tail: Coord { x: 0, y: 0 },
-            new_head: Coord { x: 0, y: 0 },
-            old_head: Coord { x: 0, y: 0 },
-            ate_food: false,
-            old_health: borrow.health.clone(),
-        };
+    // The board as the API describes it, with the snakes that are still in the game
+    pub fn to_board(&self) -> Board {
+        Board {
+            height: self.height,
+            width: self.width,
+            food: self.food.ones().map(|index| self.layout.coord(index)).collect(),
+            snakes: (0..self.snakes.len())
+                .filter(|snake| !self.is_eliminated(*snake))
+                .map(|snake| self.to_battlesnake(snake))
+                .collect(),
+            hazards: self.hazards.ones().map(|index| self.layout.coord(index)).collect(),
+        }
+    }
+
+    // Where the snake with API id `snake_id` is in `snakes`
+    pub fn snake_index(&self, snake_id: &str) -> usize {
+        self.ids.iter().position(|id| id == snake_id).unwrap()
+    }
+
+    // Moves a single snake, for searches where snakes take turns. Nobody is eliminated here.
+    pub fn move_snake(&mut self, snake: usize, direction: Direction) {
+        assert!(direction != Direction::None, "snake {} moved with Direction::None", snake);
+
+        self.move_snakes(&[(snake, direction)]);
+        self.verify_hash();
+    }
+
+    // Takes back the last `move_snake`
+    pub fn undo_move(&mut self) {
+        if !self.history.is_empty() {
+            self.undo_moves(1);
+            self.verify_hash();
+        }
+    }
+
+    // Hands the turn to `snake`, returning whose it was so it can be handed back. Moving doesn't
+    // pass the turn on by itself, only the search knows who is next.
+    pub fn set_turn(&mut self, snake: usize) -> usize {
+        let previous = self.to_move;
+        self.zobrist_hash ^= zobrist::turn(previous) ^ zobrist::turn(snake);
+        self.to_move = snake;
+        self.verify_hash();
+        previous
+    }
 
-        let old_head = borrow.head.clone();
-        action.old_head = old_head.clone();
-
-        let new_head = match direction {
-            Direction::Up => Coord { x: old_head.clone().x, y: old_head.clone().y + 1 },
-            Direction::Down => Coord { x: old_head.clone().x, y: old_head.clone().y - 1 },
-            Direction::Left => Coord { x: old_head.clone().x - 1, y: old_head.clone().y },
-            Direction::Right => Coord { x: old_head.clone().x + 1, y: old_head.clone().y },
-            Direction::None => {
-                println!("ERROR, tried to move a snake with Direction::None");
-                old_head.clone()
+    // Brings a coordinate that went over an edge back onto the board in wrapped games, anywhere
+    // else it is left alone
+    pub fn wrap(&self, coord: Coord) -> Coord {
+        if self.ruleset.mode.wraps() {
+            Coord {
+                x: coord.x.rem_euclid(self.width),
+                y: coord.y.rem_euclid(self.height),
             }
-        };
+        } else {
+            coord
+        }
+    }
+
+    pub fn neighbour(&self, coord: Coord, direction: Direction) -> Coord {
+        self.wrap(coord.step(direction))
+    }
 
-        action.new_head = new_head.clone();
-        self.headboard[old_head.x as usize][old_head.y as usize] = 0;
-        self.headboard[new_head.x as usize][new_head.y as usize] = borrow.length.clone();
+    pub fn in_bounds(&self, coord: Coord) -> bool {
+        coord.x >= 0 && coord.x < self.width && coord.y >= 0 && coord.y < self.height
+    }
 
-        // Add new head to the snake body
-        borrow.body.insert(0, new_head.clone());
-        borrow.head = new_head.clone();
+    pub fn is_eliminated(&self, snake: usize) -> bool {
+        self.eliminated[snake].is_some()
+    }
 
-        // Remove the tail of the snake
-        let old_tail = borrow.body.pop().unwrap();
-        action.old_tail = old_tail.clone();
+    // Every cell under a snake that is still in the game
+    pub fn occupied(&self) -> Bitboard {
+        let mut occupied = Bitboard::default();
+        for (body, eliminated) in self.bodies.iter().zip(&self.eliminated) {
+            if eliminated.is_none() {
+                occupied |= *body;
+            }
+        }
+        occupied
+    }
 
-        self.zobrist_hash ^= self.health_zobrist_table[(borrow.health - 1) as usize];
-        borrow.health -= 1;
+    // `coord` has to be on the board
+    pub fn is_food(&self, coord: Coord) -> bool {
+        self.food.get(self.layout.index(coord))
+    }
 
-        // Check if snake stepped on food
-        if self.matrix[new_head.x as usize][new_head.y as usize] == CellContent::Food {
-            borrow.health = 100; // Refill health
-            self.zobrist_hash ^= self.health_zobrist_table[(borrow.health - 1) as usize];
-            borrow.body.push(old_tail.clone()); // Make the snake longer fatass
-            borrow.length += 1;
-            action.ate_food = true;
-            self.zobrist_hash ^= self.zobrist_table[((new_head.x * self.height * 2) + (new_head.y * 2) + 0) as usize];
+    // Whether a head moving onto `coord` would run into a body. Tails move out of the way first,
+    // unless they are stacked after eating or it's constrictor.
+    fn is_blocked(&self, coord: Coord) -> bool {
+        let index = self.layout.index(coord);
+        let constrictor = self.ruleset.mode.constricts();
 
-            self.zobrist_hash ^= self.zobrist_table[((old_tail.x * self.height * 2) + (old_tail.y * 2) + 1) as usize];
-        } else {
-            if borrow.health > 0 {
-                self.zobrist_hash ^= self.health_zobrist_table[(borrow.health - 1) as usize];
+        self.snakes.iter().enumerate().any(|(i, snake)| {
+            if self.eliminated[i].is_some() || !self.bodies[i].get(index) {
+                return false;
+            }
+
+            let body = &snake.body;
+            let last = body.len() - 1;
+            constrictor || body[last] != coord || (last > 0 && body[last - 1] == coord)
+        })
+    }
+
+    // Heads of the other snakes still in the game that are at least `length` long
+    fn heads_at_least(&self, length: i32, snake: usize) -> Bitboard {
+        let mut heads = Bitboard::default();
+        for (i, other) in self.snakes.iter().enumerate() {
+            if i != snake && self.eliminated[i].is_none() && other.length >= length && self.in_bounds(other.head()) {
+                heads.set(self.layout.index(other.head()));
             }
-            let new_tail = borrow.body.last().unwrap().clone();
-            self.boolboard[new_tail.x as usize][new_tail.y as usize] = false;
-            action.ate_food = false;
         }
+        heads
+    }
 
-        // no need to worry about health
+    // First 4 bits of the MOVE_MAP index: walls and bodies that are UP, LEFT, DOWN, RIGHT of `head`
+    fn obstacle_bits(&self, head: Coord) -> usize {
+        let mut num = 0;
 
-        // TODO handle hazards
+        for (bit, direction) in [Direction::Up, Direction::Left, Direction::Down, Direction::Right].iter().enumerate() {
+            let next = self.neighbour(head, *direction);
+            if !self.in_bounds(next) || self.is_blocked(next) {
+                num |= 1 << bit;
+            }
+        }
 
-        // Update the game board
-        // removing the old tail MUST GO FIRST
-        self.matrix[old_tail.x as usize][old_tail.y as usize] = CellContent::Empty;
-        self.zobrist_hash ^= self.zobrist_table[((old_tail.x * self.height * 2) + (old_tail.y * 2) + 1) as usize];
-        self.board_hash ^= 1 << (old_tail.x * self.height + old_tail.y);
+        num
+    }
 
-        self.matrix[new_head.x as usize][new_head.y as usize] = CellContent::Snake(borrow.id.clone());
-        self.zobrist_hash ^= self.zobrist_table[((new_head.x * self.height * 2) + (new_head.y * 2) + 1) as usize];
-        self.board_hash ^= 1 << (new_head.x * self.height + new_head.y);
+    pub fn generate_possible_moves(&self, snake: usize) -> &'static [Direction] {
+        let old_head = self.snakes[snake].head();
+        let mut num = self.obstacle_bits(old_head);
 
-        self.boolboard[new_head.x as usize][new_head.y as usize] = true;
+        let heads = self.heads_at_least(self.snakes[snake].length, snake);
+        if !heads.is_empty() {
+            // UP-UP, UP-LEFT, LEFT-LEFT, DOWN-LEFT, DOWN-DOWN, DOWN-RIGHT, RIGHT-RIGHT, UP-RIGHT
+            let offsets = [(0, 2), (-1, 1), (-2, 0), (-1, -1), (0, -2), (1, -1), (2, 0), (1, 1)];
+            for (bit, (dx, dy)) in offsets.iter().enumerate() {
+                let coord = self.wrap(Coord { x: old_head.x + dx, y: old_head.y + dy });
+                if self.in_bounds(coord) && heads.get(self.layout.index(coord)) {
+                    num |= 1 << (bit + 4);
+                }
+            }
+        }
+
+        MOVE_MAP[num]
+    }
+
+    // Moves that don't run into a wall or a body, head-to-heads are left to `step`
+    pub fn generate_legal_moves(&self, snake: usize) -> &'static [Direction] {
+        MOVE_MAP[self.obstacle_bits(self.snakes[snake].head())]
+    }
 
-        self.history.push(action.clone());
+    // What to play when there's nothing better to go on: the first move that isn't risky or,
+    // when every move is, the first that at least stays on the board and off every body
+    pub fn first_safe_move(&self, snake: usize) -> Option<Direction> {
+        self.generate_possible_moves(snake).first()
+            .or_else(|| self.generate_legal_moves(snake).first())
+            .copied()
+    }
+
+    // Moves every snake at once, the way the game server does it: every tail leaves before any
+    // head arrives, food under several heads feeds all of them, and a snake that ate grows by
+    // stacking its new tail rather than keeping the old one, as does everybody in constrictor. Whoever
+    // ends up in a hazard without eating takes the ruleset's hazard damage on top of the usual 1. Nobody
+    // is eliminated here, that's what `step` is for. A head may leave the board, it just isn't written anywhere.
+    pub fn move_snakes(&mut self, moves: &[(usize, Direction)]) {
+        let first = self.history.len();
+        for (snake, direction) in moves {
+            self.push_action(*snake, *direction);
+        }
+        self.apply_actions(first);
+    }
 
-        // println!("Move took: {:?}", start_time.elapsed());
-        return true;
+    // Everybody eats whatever was there before anything moved, so the actions of a joint move are
+    // all recorded before any of them is applied
+    fn push_action(&mut self, snake: usize, direction: Direction) {
+        let old_head = self.snakes[snake].head();
+        let new_head = self.neighbour(old_head, direction);
+        let ate_food = self.in_bounds(new_head) && self.is_food(new_head);
+
+        self.history.push(Action {
+            snake,
+            direction,
+            old_tail: self.snakes[snake].tail(),
+            new_head,
+            old_head,
+            ate_food,
+            grew: ate_food || self.ruleset.mode.constricts(),
+            old_health: self.snakes[snake].health,
+            took_food: false,
+            head_landed_on_body: false,
+            tail_cleared: false,
+        });
     }
 
-    pub fn undo_move(&mut self, snake: Rc<RefCell<Battlesnake>>) {
-        if let Some(action) = self.history.pop() {
-            let start_time = std::time::Instant::now();
-            // let snake = self.get_snake(&action.snake_id).clone();
-            let mut borrow = snake.borrow_mut();
+    // Plays the actions from `first` on, see `move_snakes`
+    fn apply_actions(&mut self, first: usize) {
+        let height = self.height;
+
+        // Bodies and health
+        for k in first..self.history.len() {
+            let mut action = self.history[k];
+            let in_hazard = self.in_bounds(action.new_head) && self.hazards.get(self.layout.index(action.new_head));
+            let mut hash = self.zobrist_hash
+                ^ self.snake_ends_hash(action.snake)
+                ^ self.segment_key(action.snake, action.new_head, Segment::Body)
+                ^ self.segment_key(action.snake, action.old_tail, Segment::Body);
+            let snake = &mut self.snakes[action.snake];
 
-            self.zobrist_hash ^= self.zobrist_table[((action.new_head.x * self.height * 2) + (action.new_head.y * 2) + 1) as usize];
-            self.board_hash ^= 1 << (action.new_head.x * self.height + action.new_head.y);
+            snake.body.push_front(action.new_head);
+            snake.body.pop_back();
 
-            if action.ate_food {
-                self.matrix[action.new_head.x as usize][action.new_head.y as usize] = CellContent::Food;
-                self.zobrist_hash ^= self.zobrist_table[((action.new_head.x * self.height * 2) + (action.new_head.y * 2) + 0) as usize];
-                borrow.length -= 1;
+            if action.grew {
+                snake.health = 100;
+                snake.length += 1;
 
-                self.zobrist_hash ^= self.zobrist_table[((action.old_tail.x * self.height * 2) + (action.old_tail.y * 2) + 1) as usize];
-                self.board_hash ^= 1 << (action.old_tail.x * self.height + action.old_tail.y);
+                let new_tail = snake.tail();
+                snake.body.push_back(new_tail);
             } else {
-                self.matrix[action.new_head.x as usize][action.new_head.y as usize] = CellContent::Empty;
-                let new_tail = borrow.body.last().unwrap().clone();
-                borrow.body.push(action.old_tail.clone());
-                self.boolboard[new_tail.x as usize][new_tail.y as usize] = true;
+                snake.health -= 1;
+                if in_hazard {
+                    snake.health = (snake.health - self.ruleset.hazard_damage_per_turn).max(0);
+                }
             }
-            self.boolboard[action.new_head.x as usize][action.new_head.y as usize] = false;
 
-            self.matrix[action.old_tail.x as usize][action.old_tail.y as usize] = CellContent::Snake(borrow.id.clone());
-            self.zobrist_hash ^= self.zobrist_table[((action.old_tail.x * self.height * 2) + (action.old_tail.y * 2) + 1) as usize];
-            self.board_hash ^= 1 << (action.old_tail.x * self.height + action.old_tail.y);
+            // A stacked tail only frees its cell once the last copy leaves
+            if snake.tail() != action.old_tail {
+                self.bodies[action.snake].clear(self.layout.index(action.old_tail));
+                action.tail_cleared = true;
+            }
 
-            if borrow.health > 0 {
-                self.zobrist_hash ^= self.health_zobrist_table[(borrow.health - 1) as usize];
+            if action.grew {
+                hash ^= self.segment_key(action.snake, self.snakes[action.snake].tail(), Segment::Body);
             }
+            self.zobrist_hash = hash ^ self.snake_ends_hash(action.snake);
+            self.history[k] = action;
+        }
 
-            borrow.health = action.old_health.clone();
-            self.zobrist_hash ^= self.health_zobrist_table[(borrow.health - 1) as usize];
+        // Heads
+        for k in first..self.history.len() {
+            let action = &mut self.history[k];
+            let new_head = action.new_head;
+            if !(new_head.x >= 0 && new_head.x < self.width && new_head.y >= 0 && new_head.y < height) {
+                continue;
+            }
 
-            self.headboard[action.new_head.x as usize][action.new_head.y as usize] = 0;
-            self.headboard[action.old_head.x as usize][action.old_head.y as usize] = borrow.length.clone();
+            let index = self.layout.index(new_head);
+            if self.food.get(index) {
+                self.food.clear(index);
+                self.zobrist_hash ^= zobrist::food(index);
+                action.took_food = true;
+            }
 
-            borrow.body.remove(0);
-            borrow.head = action.old_head.clone();
-            // println!("Undo took: {:?}", start_time.elapsed());
+            action.head_landed_on_body = self.bodies[action.snake].get(index);
+            self.bodies[action.snake].set(index);
         }
     }
 
-    pub fn generate_possible_moves(&self, snake: Rc<RefCell<Battlesnake>>) -> Vec<Direction> {
-        let start_time = std::time::Instant::now();
+    // Takes back the last `count` moves, everything one `move_snakes` did if it is given as many
+    // snakes as that moved
+    pub fn undo_moves(&mut self, count: usize) {
+        let first = self.history.len() - count;
 
-        let mut num = 0;
+        // Heads go first so that a tail that was moved into gets its cell back afterwards
+        for k in (first..self.history.len()).rev() {
+            let action = self.history[k];
+            let new_head = action.new_head;
+
+            self.zobrist_hash ^= self.snake_ends_hash(action.snake) ^ self.segment_key(action.snake, new_head, Segment::Body);
 
-        let old_head = snake.borrow().head.clone();
-        let up_x = old_head.x;
-        let up_y = old_head.y + 1;
-        let left_x = old_head.x - 1;
-        let left_y = old_head.y;
-        let down_x = old_head.x;
-        let down_y = old_head.y - 1;
-        let right_x = old_head.x + 1;
-        let right_y = old_head.y;
-
-        if up_y >= self.height
-            || self.boolboard[up_x as usize][up_y as usize] {
-            num |= 1 << 0;
+            if self.in_bounds(new_head) {
+                let index = self.layout.index(new_head);
+                if !action.head_landed_on_body {
+                    self.bodies[action.snake].clear(index);
+                }
+
+                if action.took_food {
+                    self.food.set(index);
+                    self.zobrist_hash ^= zobrist::food(index);
+                }
+            }
+
+            self.snakes[action.snake].body.pop_front();
         }
-        if left_x < 0
-            || self.boolboard[left_x as usize][left_y as usize] {
-            num |= 1 << 1;
+
+        for k in (first..self.history.len()).rev() {
+            let action = self.history[k];
+            let mut hash = self.zobrist_hash ^ self.segment_key(action.snake, action.old_tail, Segment::Body);
+            let snake = &mut self.snakes[action.snake];
+
+            snake.health = action.old_health;
+
+            let mut grown_tail = None;
+            if action.grew {
+                grown_tail = Some(snake.body.pop_back());
+                snake.length -= 1;
+            }
+
+            if action.tail_cleared {
+                self.bodies[action.snake].set(self.layout.index(action.old_tail));
+            }
+
+            snake.body.push_back(action.old_tail);
+
+            if let Some(grown_tail) = grown_tail {
+                hash ^= self.segment_key(action.snake, grown_tail, Segment::Body);
+            }
+            self.zobrist_hash = hash ^ self.snake_ends_hash(action.snake);
         }
-        if down_y < 0
-            || self.boolboard[down_x as usize][down_y as usize] {
-            num |= 1 << 2;
+
+        self.history.truncate(first);
+    }
+
+    // Plays one full turn by the official rules: every snake still in the game moves, loses health,
+    // eats, and then whoever didn't survive is taken off the board. Snakes missing from `moves`
+    // get the move the game server would pick for them. Food is not spawned, see `spawn_food`.
+    // See https://docs.battlesnake.com/guides/game/rules
+    pub fn step(&mut self, moves: &[(usize, Direction)]) -> Vec<Elimination> {
+        let first = self.history.len();
+        let mut movers = 0;
+
+        for i in 0..self.snakes.len() {
+            if self.eliminated[i].is_some() {
+                continue;
+            }
+
+            let direction = moves.iter()
+                .find(|(snake, _)| *snake == i)
+                .map(|(_, direction)| *direction)
+                .unwrap_or_else(|| self.default_move(&self.snakes[i]));
+
+            movers |= 1 << i;
+            self.push_action(i, direction);
         }
-        if right_x >= self.width
-            || self.boolboard[right_x as usize][right_y as usize] {
-            num |= 1 << 3;
+
+        self.apply_actions(first);
+
+        let mut record = StepRecord {
+            movers,
+            eliminated: 0,
+            zobrist_hash: self.zobrist_hash,
+        };
+        let eliminations = self.eliminate(record.movers);
+        self.remove_eliminated(&eliminations, &mut record);
+        self.steps.push(record);
+        self.verify_hash();
+
+        eliminations
+    }
+
+    // The move the game server makes for a snake that didn't answer in time: keep going the way
+    // it was going, or up if it has no neck to tell
+    fn default_move(&self, snake: &Snake) -> Direction {
+        if snake.body.len() < 2 {
+            return Direction::Up;
         }
 
-        let up_up_x = old_head.x;
-        let up_up_y = old_head.y + 2;
-        let up_left_x = old_head.x - 1;
-        let up_left_y = old_head.y + 1;
-        let left_left_x = old_head.x - 2;
-        let left_left_y = old_head.y;
-        let left_down_x = old_head.x - 1;
-        let left_down_y = old_head.y - 1;
-        let down_down_x = old_head.x;
-        let down_down_y = old_head.y - 2;
-        let down_right_x = old_head.x + 1;
-        let down_right_y = old_head.y - 1;
-        let right_right_x = old_head.x + 2;
-        let right_right_y = old_head.y;
-        let up_right_x = old_head.x + 1;
-        let up_right_y = old_head.y + 1;
-
-        // TODO when it's time to deploy, make this >= and not >
-        if up_up_y < self.height
-            && self.headboard[up_up_x as usize][up_up_y as usize] >= snake.borrow().length {
-            num |= 1 << 4;
+        [Direction::Up, Direction::Down, Direction::Left, Direction::Right].iter().copied()
+            .find(|direction| snake.body[1] != snake.body[0] && self.neighbour(snake.body[1], *direction) == snake.body[0])
+            .unwrap_or(Direction::Up)
+    }
+
+    // Takes back the last `step`
+    pub fn undo_step(&mut self) {
+        let record = self.steps.pop().unwrap();
+
+        self.zobrist_hash = record.zobrist_hash;
+        for i in 0..self.snakes.len() {
+            if record.eliminated & (1 << i) != 0 {
+                self.eliminated[i] = None;
+            }
         }
-        if up_left_x >= 0 && up_left_y < self.height
-            && self.headboard[up_left_x as usize][up_left_y as usize] >= snake.borrow().length {
-            num |= 1 << 5;
+
+        self.undo_moves(record.movers.count_ones() as usize);
+        self.verify_hash();
+    }
+
+    // Same order as the reference rules: health and walls first, then collisions are decided
+    // all at once among the snakes that are still standing
+    fn eliminate(&self, movers: u64) -> Vec<Elimination> {
+        let mut eliminations = Vec::new();
+        let mut standing: u64 = 0;
+
+        for snake in &self.snakes {
+            let i = snake.index;
+            if movers & (1 << i) == 0 {
+                continue;
+            }
+
+            let cause = if snake.health <= 0 {
+                Some(EliminationCause::OutOfHealth)
+            } else if !self.in_bounds(snake.head()) {
+                Some(EliminationCause::OutOfBounds)
+            } else {
+                None
+            };
+
+            match cause {
+                Some(cause) => eliminations.push(Elimination { snake: i, cause, by: None }),
+                None => standing |= 1 << i,
+            }
         }
-        if left_left_x >= 0
-            && self.headboard[left_left_x as usize][left_left_y as usize] >= snake.borrow().length {
-            num |= 1 << 6;
+
+        let standing_snakes = || self.snakes.iter().filter(move |snake| standing & (1 << snake.index) != 0);
+        let mut collided = Vec::new();
+        for snake in standing_snakes() {
+            let (i, head) = (snake.index, snake.head());
+
+            if snake.body.iter().skip(1).any(|coord| coord == head) {
+                collided.push(Elimination { snake: i, cause: EliminationCause::SelfCollision, by: Some(i) });
+                continue;
+            }
+
+            let others = || standing_snakes().filter(|other| other.index != i);
+            if let Some(other) = others().find(|other| other.body.iter().skip(1).any(|coord| coord == head)) {
+                collided.push(Elimination { snake: i, cause: EliminationCause::Collision, by: Some(other.index) });
+                continue;
+            }
+            if let Some(other) = others().find(|other| other.head() == head && snake.length <= other.length) {
+                collided.push(Elimination { snake: i, cause: EliminationCause::HeadToHeadCollision, by: Some(other.index) });
+            }
         }
-        if left_down_x >= 0 && left_down_y >= 0
-            && self.headboard[left_down_x as usize][left_down_y as usize] >= snake.borrow().length {
-            num |= 1 << 7;
+
+        eliminations.extend(collided);
+        eliminations
+    }
+
+    // Takes eliminated snakes out of the game. Their bodies stay where they are in `bodies`, they
+    // only stop counting, so survivors sharing a cell with them keep it.
+    fn remove_eliminated(&mut self, eliminations: &[Elimination], record: &mut StepRecord) {
+        for elimination in eliminations {
+            let i = elimination.snake;
+            self.eliminated[i] = Some(elimination.cause);
+            record.eliminated |= 1 << i;
+            self.zobrist_hash ^= self.snake_hash(i);
         }
-        if down_down_y >= 0
-            && self.headboard[down_down_x as usize][down_down_y as usize] >= snake.borrow().length {
-            num |= 1 << 8;
+    }
+
+    // Tops the board up to the ruleset's minimum food, otherwise rolls for one more piece. Like the
+    // reference rules, food never lands on a cell a head could move into next turn. There is never
+    // any food in constrictor. Only for playing games out, this can't be undone.
+    pub fn spawn_food<R: Rng>(&mut self, rng: &mut R) {
+        if self.ruleset.mode.constricts() {
+            return;
         }
-        if down_right_x < self.width && down_right_y >= 0
-            && self.headboard[down_right_x as usize][down_right_y as usize] >= snake.borrow().length {
-            num |= 1 << 9;
+
+        let food = self.food.count_ones() as i32;
+
+        let wanted = if food < self.ruleset.minimum_food {
+            self.ruleset.minimum_food - food
+        } else if self.ruleset.food_spawn_chance > 0 && rng.gen_range(0..100) < self.ruleset.food_spawn_chance {
+            1
+        } else {
+            0
+        };
+        if wanted == 0 {
+            return;
         }
-        if right_right_x < self.width
-            && self.headboard[right_right_x as usize][right_right_y as usize] >= snake.borrow().length {
-            num |= 1 << 10;
+
+        let mut heads = Bitboard::default();
+        for (i, snake) in self.snakes.iter().enumerate() {
+            if self.eliminated[i].is_none() && self.in_bounds(snake.head()) {
+                heads.set(self.layout.index(snake.head()));
+            }
         }
-        if up_right_x < self.width && up_right_y < self.height
-            && self.headboard[up_right_x as usize][up_right_y as usize] >= snake.borrow().length {
-            num |= 1 << 11;
+
+        let taken = self.occupied() | self.food | self.hazards | self.layout.neighbours(heads);
+        let free: Vec<usize> = (self.layout.all() & !taken).ones().collect();
+
+        for index in free.choose_multiple(rng, wanted as usize) {
+            self.food.set(*index);
+            self.zobrist_hash ^= zobrist::food(*index);
         }
+        self.verify_hash();
+    }
 
-        // println!("Generate possible moves took: {:?}", start_time.elapsed());
-        self.move_map[num].clone()
+    // Turns `coord` into hazard for the rest of the game, the way royale's safe area shrinks. Only
+    // for playing games out, this can't be undone either.
+    pub fn add_hazard(&mut self, coord: Coord) {
+        let index = self.layout.index(coord);
+        if !self.hazards.get(index) {
+            self.hazards.set(index);
+            self.zobrist_hash ^= zobrist::hazard(index);
+        }
+        self.verify_hash();
     }
+}
+
+// Moves out of a cell, indexed by what's around it. The first 4 bits are for obstacles that are
+// UP, LEFT, DOWN, RIGHT (in that order), the next 8 bits for snake heads at least as long as
+// ours that are of dist 2 away (Manhattan), in the order
+// UP-UP, UP-LEFT, LEFT-LEFT, DOWN-LEFT, DOWN-DOWN, DOWN-RIGHT, RIGHT-RIGHT, UP-RIGHT
+static MOVE_MAP: [&[Direction]; 4096] = [
+    &[Direction::Up, Direction::Left, Direction::Down, Direction::Right],
+    &[Direction::Left, Direction::Down, Direction::Right],
+    &[Direction::Up, Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Up, Direction::Left, Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left, Direction::Down, Direction::Right],
+    &[Direction::Left, Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Up, Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Down, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Left, Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Left, Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Left, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up, Direction::Right],
+    &[Direction::Right],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[Direction::Right],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up, Direction::Left],
+    &[Direction::Left],
+    &[Direction::Up],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[Direction::Up],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Left, Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[Direction::Down],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[Direction::Left],
+    &[Direction::Left],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+];
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use super::*;
 
-    pub fn get_snake(&self, snake_id: &str) -> &Rc<RefCell<Battlesnake>> {
-        self.snakes.iter().find(|s| s.borrow().id == snake_id).unwrap()
+    fn snake(id: &str, health: i32, body: &[(i32, i32)]) -> Battlesnake {
+        let body: Vec<Coord> = body.iter().map(|&(x, y)| Coord { x, y }).collect();
+        Battlesnake {
+            id: id.to_string(),
+            name: id.to_string(),
+            health,
+            head: body[0],
+            length: body.len() as i32,
+            body,
+            latency: String::new(),
+            shout: None,
+        }
     }
 
-    pub fn clone(&self) -> Self {
-        let matrix = self.matrix.clone();
-        let width = self.width.clone();
-        let height = self.height.clone();
+    fn coords(cells: &[(i32, i32)]) -> Vec<Coord> {
+        cells.iter().map(|&(x, y)| Coord { x, y }).collect()
+    }
 
-        let snakes = self.snakes.iter().map(|snake| {
-            let snake_clone = snake.borrow().clone();
-            Rc::new(RefCell::new(snake_clone))
-        }).collect();
+    fn board(snakes: Vec<Battlesnake>, food: &[(i32, i32)], hazards: &[(i32, i32)]) -> GameBoard {
+        GameBoard::new(11, 11, coords(food), snakes, coords(hazards), Ruleset::default())
+    }
 
-        let history = self.history.clone();
+    // Everything a step changes, to check an undo against
+    #[derive(Debug, PartialEq)]
+    struct Snapshot {
+        snakes: Vec<(Vec<Coord>, i32, i32)>,
+        bodies: Vec<Bitboard>,
+        food: Bitboard,
+        eliminated: Vec<Option<EliminationCause>>,
+        zobrist_hash: u64,
+    }
 
-        Self {
-            width,
-            height,
-            matrix,
-            snakes,
-            history,
-            zobrist_hash: self.zobrist_hash.clone(),
-            health_zobrist_table: self.health_zobrist_table.clone(),
-            zobrist_table: self.zobrist_table.clone(),
-            boolboard: self.boolboard.clone(),
-            headboard: self.headboard.clone(),
-            move_map: self.move_map.clone(),
-            board_hash: self.board_hash.clone(),
+    fn snapshot(board: &GameBoard) -> Snapshot {
+        Snapshot {
+            snakes: board.snakes.iter().map(|snake| (snake.body.iter().collect(), snake.health, snake.length)).collect(),
+            bodies: board.bodies.clone(),
+            food: board.food,
+            eliminated: board.eliminated.clone(),
+            zobrist_hash: board.zobrist_hash,
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn longer_snake_wins_a_head_to_head() {
+        let mut board = board(vec![
+            snake("a", 90, &[(1, 1), (0, 1), (0, 2), (0, 3)]),
+            snake("b", 90, &[(3, 1), (4, 1), (5, 1)]),
+        ], &[], &[]);
+
+        let eliminations = board.step(&[(0, Direction::Right), (1, Direction::Left)]);
+
+        assert_eq!(eliminations, vec![
+            Elimination { snake: 1, cause: EliminationCause::HeadToHeadCollision, by: Some(0) },
+        ]);
+        assert!(!board.is_eliminated(0));
+    }
+
+    #[test]
+    fn equal_snakes_both_lose_a_head_to_head() {
+        let mut board = board(vec![
+            snake("a", 90, &[(1, 1), (0, 1), (0, 2)]),
+            snake("b", 90, &[(3, 1), (4, 1), (5, 1)]),
+        ], &[], &[]);
+
+        let eliminations = board.step(&[(0, Direction::Right), (1, Direction::Left)]);
+
+        assert_eq!(eliminations, vec![
+            Elimination { snake: 0, cause: EliminationCause::HeadToHeadCollision, by: Some(1) },
+            Elimination { snake: 1, cause: EliminationCause::HeadToHeadCollision, by: Some(0) },
+        ]);
+    }
+
+    #[test]
+    fn stacked_tail_after_eating_stays_in_the_way() {
+        let mut board = board(vec![
+            snake("a", 50, &[(5, 5), (5, 6), (5, 7)]),
+            snake("b", 90, &[(7, 6), (8, 6), (9, 6)]),
+        ], &[(5, 4)], &[]);
+
+        assert!(board.step(&[(0, Direction::Down), (1, Direction::Left)]).is_empty());
+        assert_eq!(board.snakes[0].body.iter().collect::<Vec<_>>(), coords(&[(5, 4), (5, 5), (5, 6), (5, 6)]));
+        assert_eq!((board.snakes[0].health, board.snakes[0].length), (100, 4));
+
+        // The tail that was stacked by eating doesn't move this turn, so b runs into it
+        let eliminations = board.step(&[(0, Direction::Down), (1, Direction::Left)]);
+
+        assert_eq!(eliminations, vec![
+            Elimination { snake: 1, cause: EliminationCause::Collision, by: Some(0) },
+        ]);
+    }
+
+    #[test]
+    fn snakes_starve_unless_they_eat() {
+        let mut board = board(vec![
+            snake("a", 1, &[(1, 1), (1, 2), (1, 3)]),
+            snake("b", 1, &[(5, 5), (5, 6), (5, 7)]),
+        ], &[(5, 4)], &[]);
+
+        let eliminations = board.step(&[(0, Direction::Down), (1, Direction::Down)]);
+
+        assert_eq!(eliminations, vec![
+            Elimination { snake: 0, cause: EliminationCause::OutOfHealth, by: None },
+        ]);
+        assert_eq!(board.snakes[1].health, 100);
+    }
+
+    #[test]
+    fn hazards_only_hurt_snakes_that_dont_eat() {
+        let mut board = board(vec![
+            snake("a", 50, &[(1, 1), (1, 2), (1, 3)]),
+            snake("b", 50, &[(5, 5), (5, 6), (5, 7)]),
+        ], &[(5, 4)], &[(1, 0), (5, 4)]);
+
+        assert!(board.step(&[(0, Direction::Down), (1, Direction::Down)]).is_empty());
+
+        assert_eq!(board.snakes[0].health, 50 - 1 - 14);
+        assert_eq!((board.snakes[1].health, board.snakes[1].length), (100, 4));
+    }
+
+    #[test]
+    fn health_and_walls_are_decided_before_collisions() {
+        let mut board = board(vec![
+            snake("a", 90, &[(3, 3), (2, 3), (1, 3)]),
+            snake("b", 90, &[(4, 4), (4, 3), (4, 2), (4, 1)]),
+            snake("c", 90, &[(0, 9), (1, 9), (2, 9)]),
+        ], &[], &[]);
+
+        let eliminations = board.step(&[(0, Direction::Right), (1, Direction::Up), (2, Direction::Left)]);
+
+        assert_eq!(eliminations, vec![
+            Elimination { snake: 2, cause: EliminationCause::OutOfBounds, by: None },
+            Elimination { snake: 0, cause: EliminationCause::Collision, by: Some(1) },
+        ]);
+    }
+
+    #[test]
+    fn starved_snakes_are_not_run_into() {
+        let mut board = board(vec![
+            snake("a", 90, &[(3, 3), (2, 3), (1, 3)]),
+            snake("b", 1, &[(4, 4), (4, 3), (4, 2)]),
+        ], &[], &[]);
+
+        let eliminations = board.step(&[(0, Direction::Right), (1, Direction::Up)]);
+
+        assert_eq!(eliminations, vec![
+            Elimination { snake: 1, cause: EliminationCause::OutOfHealth, by: None },
+        ]);
+        assert!(!board.is_eliminated(0));
+    }
+
+    #[test]
+    fn undo_step_restores_bodies_health_and_hash() {
+        let mut board = board(vec![
+            snake("a", 50, &[(5, 5), (5, 6), (5, 7)]),
+            snake("b", 50, &[(1, 1), (1, 2), (1, 3)]),
+            snake("c", 90, &[(0, 9), (1, 9), (2, 9)]),
+        ], &[(5, 4)], &[(1, 0)]);
+        let before = snapshot(&board);
+
+        board.step(&[(0, Direction::Down), (1, Direction::Down), (2, Direction::Left)]);
+        let after_one = snapshot(&board);
+        assert_ne!(after_one, before);
+
+        board.step(&[(0, Direction::Down), (1, Direction::Right)]);
+        assert_eq!(board.zobrist_hash, board.compute_hash());
+
+        board.undo_step();
+        assert_eq!(snapshot(&board), after_one);
+        board.undo_step();
+        assert_eq!(snapshot(&board), before);
+        assert_eq!(board.zobrist_hash, board.compute_hash());
+    }
+
+    #[test]
+    fn hazards_are_part_of_the_hash() {
+        let snakes = || vec![snake("a", 90, &[(1, 1), (1, 2), (1, 3)])];
+        let mut shrunk = board(snakes(), &[], &[]);
+        let before = shrunk.zobrist_hash;
+
+        shrunk.add_hazard(Coord { x: 5, y: 5 });
+
+        assert_ne!(shrunk.zobrist_hash, before);
+        assert_eq!(shrunk.zobrist_hash, shrunk.compute_hash());
+        assert_eq!(shrunk.zobrist_hash, board(snakes(), &[], &[(5, 5)]).zobrist_hash);
+    }
+
+    #[test]
+    fn whose_turn_it_is_changes_the_hash() {
+        let mut board = board(vec![
+            snake("a", 90, &[(1, 1), (1, 2), (1, 3)]),
+            snake("b", 90, &[(8, 8), (8, 9), (8, 10)]),
+        ], &[], &[]);
+        let before = board.zobrist_hash;
+
+        assert_eq!(board.set_turn(1), 0);
+        assert_ne!(board.zobrist_hash, before);
+        assert_eq!(board.zobrist_hash, board.compute_hash());
+
+        // `b` gets to move in the meantime, like it does after a null move in the search
+        board.move_snake(1, Direction::Left);
+        board.undo_move();
+        board.set_turn(0);
+        assert_eq!(board.zobrist_hash, before);
+    }
+
+    #[test]
+    fn move_orders_that_meet_hash_the_same() {
+        let snakes = || vec![
+            snake("a", 90, &[(1, 1), (1, 2), (1, 3)]),
+            snake("b", 90, &[(5, 5), (5, 6), (5, 7)]),
+            snake("c", 90, &[(8, 8), (8, 9), (8, 10)]),
+        ];
+
+        // a, b and then c's turn, against b, a with c stuck in between and then c's turn again
+        let mut first = board(snakes(), &[], &[]);
+        first.move_snake(0, Direction::Right);
+        first.set_turn(1);
+        first.move_snake(1, Direction::Right);
+        first.set_turn(2);
+
+        let mut second = board(snakes(), &[], &[]);
+        second.set_turn(1);
+        second.move_snake(1, Direction::Right);
+        second.set_turn(2);
+        second.set_turn(0);
+        second.move_snake(0, Direction::Right);
+        second.set_turn(2);
+
+        assert_eq!(first.zobrist_hash, second.zobrist_hash);
+        assert_eq!(first.zobrist_hash, first.compute_hash());
+    }
+
+    #[test]
+    fn a_risky_move_is_safer_than_none() {
+        // The only way out of the corner is next to a longer head
+        let board = board(vec![
+            snake("a", 90, &[(0, 0), (1, 0), (2, 0)]),
+            snake("b", 90, &[(0, 2), (1, 2), (2, 2), (3, 2)]),
+        ], &[], &[]);
+
+        assert!(board.generate_possible_moves(0).is_empty());
+        assert_eq!(board.first_safe_move(0), Some(Direction::Up));
+        assert_eq!(board.first_safe_move(1), board.generate_possible_moves(1).first().copied());
+    }
+
+    #[test]
+    fn food_never_spawns_next_to_a_head() {
+        let ruleset = Ruleset { minimum_food: 9, ..Ruleset::default() };
+        let mut board = GameBoard::new(3, 3, vec![], vec![snake("a", 90, &[(1, 1), (1, 1), (1, 1)])], vec![], ruleset);
+
+        board.spawn_food(&mut StdRng::seed_from_u64(1));
+
+        let mut food: Vec<Coord> = board.food.ones().map(|index| board.layout.coord(index)).collect();
+        food.sort_by_key(|coord| (coord.x, coord.y));
+        assert_eq!(food, coords(&[(0, 0), (0, 2), (2, 0), (2, 2)]));
+    }
+}
@@ -0,0 +1,7 @@
+use std::str::FromStr;
+
+// The value after `option` on a command line, parsed
+pub fn value<T: FromStr>(option: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", option))?;
+    value.parse().map_err(|_| format!("invalid value {:?} for {}", value, option))
+}
@@ -0,0 +1,130 @@
+use std::cell::RefCell;
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use log::warn;
+use crate::game_recorder::{TrainingDataFiles, TrainingDataFormat};
+use crate::search::{SearchMode, SimultaneousBackup};
+
+// Engine settings, read once at launch. Every field can be overridden with the
+// environment variable named next to it so deployments can be tuned without a rebuild.
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    // MOVE_SAFETY_MARGIN_MS: time held back from the move timeout on top of the measured latency
+    pub(crate) safety_margin_ms: u64,
+    // SOFT_DEADLINE_PERCENT: share of the budget after which no new iteration is started
+    pub(crate) soft_deadline_percent: u64,
+    // MAX_SEARCH_DEPTH: deepest iteration the search will attempt, whatever the clock says
+    pub(crate) max_depth: i32,
+    // SEARCH_MODE: `sequential` (snakes take turns) or `simultaneous` (every ply is a joint move)
+    pub(crate) search_mode: SearchMode,
+    // SIMULTANEOUS_BACKUP: `pessimistic` (enemies see our move) or `mixed` (solve each joint move as a matrix game)
+    pub(crate) simultaneous_backup: SimultaneousBackup,
+    // SEARCH_THREADS: threads searching each move together, every core the host has by default
+    pub(crate) threads: usize,
+    // TT_SIZE_MB: transposition table size for each game
+    pub(crate) tt_size_mb: usize,
+    // ENGINE_SEED: where every game's random numbers come from, a fresh one on every launch
+    pub(crate) seed: u64,
+    // GAME_IDLE_TIMEOUT_SECS: games without a request for this long are dropped, `/end` may never come
+    pub(crate) game_idle_timeout_secs: u64,
+    // MAX_GAMES: games tracked at once, the least recently used one goes to make room for another
+    pub(crate) max_games: usize,
+    // TRAINING_DATA_DIR: where the rounds of finished games are written
+    pub(crate) training_data_dir: PathBuf,
+    // TRAINING_DATA_FILES: `game` (a file per game id) or `day` (a file per UTC day)
+    pub(crate) training_data_files: TrainingDataFiles,
+    // TRAINING_DATA_FORMAT: `csv`, `jsonl`, `binary` or `off`
+    pub(crate) training_data_format: TrainingDataFormat,
+    // RECORD_REPLAYS: whether every request of a game and our answers are kept in a replay file
+    pub(crate) record_replays: bool,
+    // REPLAY_DIR: where replay files go, one per game
+    pub(crate) replay_dir: PathBuf,
+    // PRINT_SEARCHES: whether every move's search logs a summary and its PV
+    pub(crate) print_searches: bool,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            safety_margin_ms: 60,
+            soft_deadline_percent: 50,
+            max_depth: 64,
+            search_mode: SearchMode::Sequential,
+            simultaneous_backup: SimultaneousBackup::Pessimistic,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            tt_size_mb: 16,
+            seed: rand::random(),
+            game_idle_timeout_secs: 300,
+            max_games: 32,
+            training_data_dir: PathBuf::from("training_data"),
+            training_data_files: TrainingDataFiles::PerGame,
+            training_data_format: TrainingDataFormat::Csv,
+            record_replays: true,
+            replay_dir: PathBuf::from("replays"),
+            print_searches: true,
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn from_env() -> Self {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    // Like `from_env`, with every (KEY, VALUE) of `overrides` taking the place of the environment
+    // variable it names. Fails on a key that isn't a setting.
+    pub fn with_overrides(overrides: &[(String, String)]) -> Result<Self, String> {
+        let asked = RefCell::new(Vec::new());
+        let config = Self::from_vars(|key| {
+            asked.borrow_mut().push(key.to_string());
+            overrides.iter().rev()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+                .or_else(|| env::var(key).ok())
+        });
+
+        match overrides.iter().find(|(name, _)| !asked.borrow().contains(name)) {
+            Some((name, _)) => Err(format!("unknown setting {}", name)),
+            None => Ok(config),
+        }
+    }
+
+    // `var` looks a setting up by its environment variable's name
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let default = Self::default();
+
+        Self {
+            safety_margin_ms: env_or(&var, "MOVE_SAFETY_MARGIN_MS", default.safety_margin_ms),
+            soft_deadline_percent: env_or(&var, "SOFT_DEADLINE_PERCENT", default.soft_deadline_percent).min(100),
+            max_depth: env_or(&var, "MAX_SEARCH_DEPTH", default.max_depth).max(1),
+            search_mode: env_or(&var, "SEARCH_MODE", default.search_mode),
+            simultaneous_backup: env_or(&var, "SIMULTANEOUS_BACKUP", default.simultaneous_backup),
+            threads: env_or(&var, "SEARCH_THREADS", default.threads).max(1),
+            tt_size_mb: env_or(&var, "TT_SIZE_MB", default.tt_size_mb).max(1),
+            seed: env_or(&var, "ENGINE_SEED", default.seed),
+            game_idle_timeout_secs: env_or(&var, "GAME_IDLE_TIMEOUT_SECS", default.game_idle_timeout_secs).max(1),
+            max_games: env_or(&var, "MAX_GAMES", default.max_games).max(1),
+            training_data_dir: env_or(&var, "TRAINING_DATA_DIR", default.training_data_dir),
+            training_data_files: env_or(&var, "TRAINING_DATA_FILES", default.training_data_files),
+            training_data_format: env_or(&var, "TRAINING_DATA_FORMAT", default.training_data_format),
+            record_replays: env_or(&var, "RECORD_REPLAYS", default.record_replays),
+            replay_dir: env_or(&var, "REPLAY_DIR", default.replay_dir),
+            print_searches: env_or(&var, "PRINT_SEARCHES", default.print_searches),
+        }
+    }
+}
+
+fn env_or<T: FromStr>(var: impl Fn(&str) -> Option<String>, key: &str, default: T) -> T {
+    match var(key) {
+        Some(value) => match value.trim().parse() {
+            Ok(parsed) => parsed,
+            Err(_) => {
+                warn!("Ignoring invalid value {:?} for {}", value, key);
+                default
+            }
+        },
+        None => default,
+    }
+}
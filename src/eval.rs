@@ -113,45 +113,76 @@
 //
 //     distances
 // }
-use std::cell::RefCell;
 use std::collections::VecDeque;
-use std::rc::Rc;
-use crate::Battlesnake;
-use crate::board::{CellContent, Direction, GameBoard};
+use crate::Coord;
+use crate::bitboard::{Bitboard, Bits, Layout};
+use crate::board::GameBoard;
+use crate::grid::{Cells, Grid, SmallGrid, SMALL_GRID_SIZE};
+use crate::snake::Snake;
+
+// A cell we own inside a hazard is worth this much, an ordinary cell is worth 100. Hazards that
+// do so little damage that they don't cost an extra step count in full.
+const HAZARD_CELL_VALUE: i32 = 40;
+// Hazard damage that makes a hazard cell count as one more step away
+const HAZARD_DAMAGE_PER_STEP: i32 = 7;
+
+// `snake` and `enemy` are indices into `board.snakes`
+pub fn eval(board: &GameBoard, snake: usize, enemy: usize) -> i32 {
+    if unit_costs(board) {
+        eval_flooded(board, snake, enemy)
+    } else if fits_small_grid(board) {
+        eval_on::<SmallGrid<i32>>(board, snake, enemy)
+    } else {
+        eval_on::<Grid<i32>>(board, snake, enemy)
+    }
+}
+
+// `eval_on` for boards where every free cell costs one step, with the territory flooded a whole
+// ring at a time. Its scores are not `eval_on`'s: `bfs` takes turns unevenly between the heads and
+// stops one at cells the other already reached, so a few cells go to a head that isn't the nearest.
+// The flood gives every cell to its nearest head, longer snakes winning ties, the way `voronoi`
+// does with any number of snakes.
+fn eval_flooded(board: &GameBoard, snake: usize, enemy: usize) -> i32 {
+    let snake = &board.snakes[snake];
+    let enemy = &board.snakes[enemy];
+
+    let territory = flood_territory(board, &[Some(snake.head()), Some(enemy.head())], &[snake.length, enemy.length]);
+    let mut score = territory[0] - territory[1];
+
+    if board.ruleset.mode.constricts() {
+        return score;
+    }
+
+    score += health_term(snake);
+    score += length_term(snake, enemy.length);
+
+    score
+}
 
-pub fn eval(board: &GameBoard, snake: Rc<RefCell<Battlesnake>>, enemy: Rc<RefCell<Battlesnake>>) -> i32 {
-    // let snake = board.get_snake(snake_id);
-    // let enemy = board.get_snake(enemy_id);
+#[allow(clippy::needless_range_loop)]
+fn eval_on<G: Cells<i32>>(board: &GameBoard, snake: usize, enemy: usize) -> i32 {
+    let snake = &board.snakes[snake];
+    let enemy = &board.snakes[enemy];
 
     let mut score: i32 = 0;
 
     // Quadratic score based on health to emphasize the danger of low health
     // score -= enemy.health / 15;
 
-    let snake_x = snake.borrow().head.x;
-    let snake_y = snake.borrow().head.y;
-    let enemy_x = enemy.borrow().head.x;
-    let enemy_y = enemy.borrow().head.y;
+    let snake_x = snake.head().x;
+    let snake_y = snake.head().y;
+    let enemy_x = enemy.head().x;
+    let enemy_y = enemy.head().y;
 
-    let snake_length = snake.borrow().length;
-    let enemy_length = enemy.borrow().length;
+    let snake_length = snake.length;
+    let enemy_length = enemy.length;
 
-    let mut passability_matrix: [[bool; 11]; 11] = [[false; 11]; 11];
+    let passability_matrix: G = passability(board);
 
-    for x in 0..board.width as usize {
-        for y in 0..board.height as usize {
-            if board.matrix[x][y] == CellContent::Empty || board.matrix[x][y] == CellContent::Food {
-                passability_matrix[x][y] = true;
-            }
-        }
-    }
-
-
-    let (snake_bfs, enemy_bfs) = bfs(&passability_matrix,
+    let (snake_bfs, enemy_bfs) = bfs(&passability_matrix, wrap_dimensions(board),
                                        snake_x as usize, snake_y as usize,
                                        enemy_x as usize, enemy_y as usize);
 
-    let mut enemys = 0;
     for x in 0..board.width as usize {
         for y in 0..board.height as usize {
             // the snake_bfs != -1 is just to make sure that the square is passable in the first place
@@ -164,19 +195,17 @@ pub fn eval(board: &GameBoard, snake: Rc<RefCell<Battlesnake>>, enemy: Rc<RefCel
             // };
             // println!("{}, {}", snake_bfs[x][y], enemy_bfs[x][y]);
             // if enemy_bfs[x][y] != -1 && snake_bfs[x][y] != -1 {
-            if passability_matrix[x][y] {
-                enemys += 1;
-
+            if passability_matrix[(x, y)] > 0 {
                 // let diff = if (5 - x as i32).abs() + (5 - y as i32).abs() < 5 {
                 //     125
                 // } else {
                 //     100
                 // };
-                let diff = 100;
+                let diff = if passability_matrix[(x, y)] > 1 { HAZARD_CELL_VALUE } else { 100 };
 
-                if snake_bfs[x][y] < enemy_bfs[x][y] {
+                if snake_bfs[(x, y)] < enemy_bfs[(x, y)] {
                     score += diff;
-                } else if enemy_bfs[x][y] < snake_bfs[x][y] {
+                } else if enemy_bfs[(x, y)] < snake_bfs[(x, y)] {
                     score -= diff;
                 } else if snake_length > enemy_length {
                     score += diff;
@@ -199,11 +228,13 @@ pub fn eval(board: &GameBoard, snake: Rc<RefCell<Battlesnake>>, enemy: Rc<RefCel
     // score /= 2;
     // score = -score;
 
-    if snake.borrow().health < 70 {
-        score += 100 * (snake.borrow().health / 12) * (snake.borrow().health / 12) - 2000;
-
+    // Nobody gets hungry and everybody grows at the same rate in constrictor, space is all there is
+    if board.ruleset.mode.constricts() {
+        return score;
     }
-    score += (snake.borrow().length - enemy.borrow().length) * 300;
+
+    score += health_term(snake);
+    score += length_term(snake, enemy.length);
     //
     // if print {
     //     println!("Length score: {}", (snake.borrow().length - enemy.borrow().length) * 3);
@@ -225,14 +256,105 @@ pub fn eval(board: &GameBoard, snake: Rc<RefCell<Battlesnake>>, enemy: Rc<RefCel
     // score += (dist_to_wall * dist_to_wall) - (enemy_dist_to_wall * enemy_dist_to_wall) / 3;
 
     // println!("{}", snake.borrow().name);
-    return score;
+    score
+}
+
+// Quadratic in health to emphasize the danger of running low
+fn health_term(snake: &Snake) -> i32 {
+    if snake.health < 70 {
+        100 * (snake.health / 12) * (snake.health / 12) - 2000
+    } else {
+        0
+    }
+}
+
+fn length_term(snake: &Snake, enemy_length: i32) -> i32 {
+    (snake.length - enemy_length) * 300
+}
+
+// What the eval of a position is made of, from the point of view of `players[0]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EvalBreakdown {
+    pub(crate) territory: i32,
+    pub(crate) health: i32,
+    pub(crate) length: i32,
+    pub(crate) total: i32,
+}
+
+// The terms `eval` (two snakes) or `eval_multi` (more) add up for `players`, nobody eliminated.
+// Territory is whatever the other terms leave of the total, so the parts always add up to it.
+pub fn eval_breakdown(board: &GameBoard, players: &[usize]) -> EvalBreakdown {
+    let total = match players {
+        [snake, enemy] => eval(board, *snake, *enemy),
+        _ => eval_multi(board, players, &vec![false; players.len()]),
+    };
+
+    let snake = &board.snakes[players[0]];
+    let longest_enemy = players[1..].iter().map(|enemy| board.snakes[*enemy].length).max().unwrap_or(0);
+    let (health, length) = if board.ruleset.mode.constricts() {
+        (0, 0)
+    } else {
+        (health_term(snake), length_term(snake, longest_enemy))
+    };
+
+    EvalBreakdown { territory: total - health - length, health, length, total }
+}
+
+// The standard board and anything smaller can use the fixed-size grids
+fn fits_small_grid(board: &GameBoard) -> bool {
+    board.width as usize <= SMALL_GRID_SIZE && board.height as usize <= SMALL_GRID_SIZE
+}
+
+// No cell costs more than one step to walk into, so territory can be flooded ring by ring. Below
+// `HAZARD_DAMAGE_PER_STEP` a hazard cell is an ordinary one to every eval, worth the full 100 and
+// not `HAZARD_CELL_VALUE`, and only the health it takes is left for the search to notice.
+fn unit_costs(board: &GameBoard) -> bool {
+    board.hazards.is_empty() || board.ruleset.hazard_damage_per_turn < HAZARD_DAMAGE_PER_STEP
+}
+
+// The board's size when walking off an edge comes back in on the other side
+pub(crate) fn wrap_dimensions(board: &GameBoard) -> Option<(i32, i32)> {
+    if board.ruleset.mode.wraps() {
+        Some((board.width, board.height))
+    } else {
+        None
+    }
 }
 
-pub(crate) fn bfs(passability_matrix: &[[bool; 11]; 11],
+// The cell one (dx, dy) step away, wrapped around `wrap` if there is one. Off the board is
+// anything past the grid's width or height.
+fn offset(x: usize, y: usize, dx: i32, dy: i32, wrap: Option<(i32, i32)>) -> (usize, usize) {
+    match wrap {
+        Some((width, height)) => ((x as i32 + dx).rem_euclid(width) as usize, (y as i32 + dy).rem_euclid(height) as usize),
+        None => ((x as i32 + dx) as usize, (y as i32 + dy) as usize),
+    }
+}
+
+// What it costs to walk into each cell, 0 for cells that can't be walked into. Hazards can be
+// crossed but are charged extra so that space behind them looks further away than it is.
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn passability<G: Cells<i32>>(board: &GameBoard) -> G {
+    let mut passability_matrix = G::filled(board.width as usize, board.height as usize, 0);
+    let hazard_cost = 1 + board.ruleset.hazard_damage_per_turn / HAZARD_DAMAGE_PER_STEP;
+    let free = board.layout.all() & !board.occupied();
+
+    for index in free.ones() {
+        let coord = board.layout.coord(index);
+        passability_matrix[(coord.x as usize, coord.y as usize)] = if board.hazards.get(index) { hazard_cost } else { 1 };
+    }
+
+    passability_matrix
+}
+
+// Distances from two heads over `passability_matrix` costs. Cells are re-queued whenever a
+// cheaper way in is found, so hazards don't have to be reached in BFS order.
+pub(crate) fn bfs<G: Cells<i32>>(passability_matrix: &G, wrap: Option<(i32, i32)>,
                     start_x_1: usize, start_y_1: usize,
-                    start_x_2: usize, start_y_2: usize) -> ([[i32; 11]; 11], [[i32; 11]; 11]) {
-    let mut distances_1: [[i32; 11]; 11] = [[9999; 11]; 11];
-    let mut distances_2: [[i32; 11]; 11] = [[9999; 11]; 11];
+                    start_x_2: usize, start_y_2: usize) -> (G, G) {
+    let width = passability_matrix.width();
+    let height = passability_matrix.height();
+    let mut distances_1 = G::filled(width, height, 9999);
+    let mut distances_2 = G::filled(width, height, 9999);
     let mut queue_1 = VecDeque::new();
     let mut queue_2 = VecDeque::new();
 
@@ -241,23 +363,23 @@ pub(crate) fn bfs(passability_matrix: &[[bool; 11]; 11],
 
     // Start with initial point
     queue_1.push_back((start_x_1, start_y_1));
-    distances_1[start_x_1][start_y_1] = 0;
+    distances_1[(start_x_1, start_y_1)] = 0;
 
     queue_2.push_back((start_x_2, start_y_2));
-    distances_2[start_x_2][start_y_2] = 0;
+    distances_2[(start_x_2, start_y_2)] = 0;
 
     while let Some((x_1, y_1)) = queue_1.pop_front() {
         for (dx, dy) in &directions {
-            let new_x_1 = (x_1 as i32 + dx) as usize;
-            let new_y_1 = (y_1 as i32 + dy) as usize;
+            let (new_x_1, new_y_1) = offset(x_1, y_1, *dx, *dy, wrap);
 
             // Check if new position is within bounds
-            if new_x_1 < 11 && new_y_1 < 11 {
+            if new_x_1 < width && new_y_1 < height {
                 // Check if the cell is passable and not visited
-                if passability_matrix[new_x_1][new_y_1] && distances_1[new_x_1][new_y_1] == 9999 {
-                    distances_1[new_x_1][new_y_1] = distances_1[x_1][y_1] + 1;
+                let distance = distances_1[(x_1, y_1)] + passability_matrix[(new_x_1, new_y_1)];
+                if passability_matrix[(new_x_1, new_y_1)] > 0 && distance < distances_1[(new_x_1, new_y_1)] {
+                    distances_1[(new_x_1, new_y_1)] = distance;
 
-                    if distances_1[new_x_1][new_y_1] <= distances_2[new_x_1][new_y_1] {
+                    if distances_1[(new_x_1, new_y_1)] <= distances_2[(new_x_1, new_y_1)] {
                         queue_1.push_back((new_x_1, new_y_1));
                     }
                 }
@@ -265,15 +387,15 @@ pub(crate) fn bfs(passability_matrix: &[[bool; 11]; 11],
 
             if let Some((x_2, y_2)) = queue_2.pop_front() {
                 for (dx, dy) in &directions {
-                    let new_x_2 = (x_2 as i32 + dx) as usize;
-                    let new_y_2 = (y_2 as i32 + dy) as usize;
+                    let (new_x_2, new_y_2) = offset(x_2, y_2, *dx, *dy, wrap);
 
-                    if new_x_2 < 11 && new_y_2 < 11 {
+                    if new_x_2 < width && new_y_2 < height {
                         // Check if the cell is passable and not visited
-                        if passability_matrix[new_x_2][new_y_2] && distances_2[new_x_2][new_y_2] == 9999 {
-                            distances_2[new_x_2][new_y_2] = distances_2[x_2][y_2] + 1;
+                        let distance = distances_2[(x_2, y_2)] + passability_matrix[(new_x_2, new_y_2)];
+                        if passability_matrix[(new_x_2, new_y_2)] > 0 && distance < distances_2[(new_x_2, new_y_2)] {
+                            distances_2[(new_x_2, new_y_2)] = distance;
 
-                            if distances_2[new_x_2][new_y_2] <= distances_1[new_x_2][new_y_2] {
+                            if distances_2[(new_x_2, new_y_2)] <= distances_1[(new_x_2, new_y_2)] {
                                 queue_2.push_back((new_x_2, new_y_2));
                             }
                         }
@@ -285,15 +407,15 @@ pub(crate) fn bfs(passability_matrix: &[[bool; 11]; 11],
 
     while let Some((x_2, y_2)) = queue_2.pop_front() {
         for (dx, dy) in &directions {
-            let new_x_2 = (x_2 as i32 + dx) as usize;
-            let new_y_2 = (y_2 as i32 + dy) as usize;
+            let (new_x_2, new_y_2) = offset(x_2, y_2, *dx, *dy, wrap);
 
-            if new_x_2 < 11 && new_y_2 < 11 {
+            if new_x_2 < width && new_y_2 < height {
                 // Check if the cell is passable and not visited
-                if passability_matrix[new_x_2][new_y_2] && distances_2[new_x_2][new_y_2] == 9999 {
-                    distances_2[new_x_2][new_y_2] = distances_2[x_2][y_2] + 1;
+                let distance = distances_2[(x_2, y_2)] + passability_matrix[(new_x_2, new_y_2)];
+                if passability_matrix[(new_x_2, new_y_2)] > 0 && distance < distances_2[(new_x_2, new_y_2)] {
+                    distances_2[(new_x_2, new_y_2)] = distance;
 
-                    if distances_2[new_x_2][new_y_2] <= distances_1[new_x_2][new_y_2] {
+                    if distances_2[(new_x_2, new_y_2)] <= distances_1[(new_x_2, new_y_2)] {
                         queue_2.push_back((new_x_2, new_y_2));
                     }
                 }
@@ -302,4 +424,359 @@ pub(crate) fn bfs(passability_matrix: &[[bool; 11]; 11],
     }
 
     (distances_1, distances_2)
-}
\ No newline at end of file
+}
+// Territory eval for games with more than two snakes, from the point of view of `players[0]`
+// against every enemy that is still in the game
+#[allow(clippy::needless_range_loop)]
+pub fn eval_multi(board: &GameBoard, players: &[usize], eliminated: &[bool]) -> i32 {
+    if unit_costs(board) {
+        eval_multi_flooded(board, players, eliminated)
+    } else if fits_small_grid(board) {
+        eval_multi_on::<SmallGrid<i32>, SmallGrid<usize>>(board, players, eliminated)
+    } else {
+        eval_multi_on::<Grid<i32>, Grid<usize>>(board, players, eliminated)
+    }
+}
+
+#[allow(clippy::needless_range_loop)]
+fn eval_multi_on<G: Cells<i32>, O: Cells<usize>>(board: &GameBoard, players: &[usize], eliminated: &[bool]) -> i32 {
+    let passability_matrix: G = passability(board);
+
+    let mut heads = Vec::with_capacity(players.len());
+    let mut lengths = Vec::with_capacity(players.len());
+    for (i, &player) in players.iter().enumerate() {
+        let snake = &board.snakes[player];
+        // An eliminated snake doesn't claim anything, but its body is still in the way
+        if !eliminated[i] {
+            heads.push((snake.head().x as usize, snake.head().y as usize));
+            lengths.push(snake.length);
+        } else {
+            heads.push((usize::MAX, usize::MAX));
+            lengths.push(0);
+        }
+    }
+
+    let territory = voronoi::<G, O>(&passability_matrix, wrap_dimensions(board), &heads, &lengths);
+
+    score_territory(board, players, eliminated, &territory, &lengths)
+}
+
+// `eval_multi_on` for boards where every free cell costs one step
+fn eval_multi_flooded(board: &GameBoard, players: &[usize], eliminated: &[bool]) -> i32 {
+    let mut heads = Vec::with_capacity(players.len());
+    let mut lengths = Vec::with_capacity(players.len());
+    for (i, &player) in players.iter().enumerate() {
+        let snake = &board.snakes[player];
+        if !eliminated[i] {
+            heads.push(Some(snake.head()));
+            lengths.push(snake.length);
+        } else {
+            heads.push(None);
+            lengths.push(0);
+        }
+    }
+
+    let territory = flood_territory(board, &heads, &lengths);
+
+    score_territory(board, players, eliminated, &territory, &lengths)
+}
+
+#[allow(clippy::needless_range_loop)]
+fn score_territory(board: &GameBoard, players: &[usize], eliminated: &[bool], territory: &[i32], lengths: &[i32]) -> i32 {
+    let mut score: i32 = 0;
+
+    let snake = &board.snakes[players[0]];
+    let mut longest_enemy = 0;
+    for i in 1..players.len() {
+        if !eliminated[i] {
+            score -= territory[i];
+            longest_enemy = longest_enemy.max(lengths[i]);
+        }
+    }
+    score += territory[0];
+
+    if board.ruleset.mode.constricts() {
+        return score;
+    }
+
+    score += health_term(snake);
+    score += length_term(snake, longest_enemy);
+
+    score
+}
+
+// Floods out from every head at once and scores the cells each snake reaches first, 100 for an
+// ordinary cell and less for one that costs extra to walk into. Cells reached at the same time go
+// to the longer snake, or to nobody if the lengths are equal.
+// Heads at (usize::MAX, usize::MAX) are ignored.
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn voronoi<G: Cells<i32>, O: Cells<usize>>(passability_matrix: &G, wrap: Option<(i32, i32)>, heads: &[(usize, usize)], lengths: &[i32]) -> Vec<i32> {
+    const CONTESTED: usize = usize::MAX;
+
+    let width = passability_matrix.width();
+    let height = passability_matrix.height();
+    let mut distances = G::filled(width, height, 9999);
+    let mut owners = O::filled(width, height, CONTESTED);
+    // Length of whoever holds the claim, so a tie can be broken by a third snake arriving later
+    let mut claim_lengths = G::filled(width, height, 0);
+    let mut queue = VecDeque::new();
+
+    // Cardinal directions
+    let directions = [(0, 1), (0, -1), (1, 0), (-1, 0)];
+
+    for (i, (x, y)) in heads.iter().enumerate() {
+        if *x < width && *y < height {
+            distances[(*x, *y)] = 0;
+            owners[(*x, *y)] = i;
+            claim_lengths[(*x, *y)] = lengths[i];
+            queue.push_back((*x, *y, i));
+        }
+    }
+
+    while let Some((x, y, owner)) = queue.pop_front() {
+        // Lost this cell to a longer snake after it was queued
+        if owners[(x, y)] != owner {
+            continue;
+        }
+
+        for (dx, dy) in &directions {
+            let (new_x, new_y) = offset(x, y, *dx, *dy, wrap);
+
+            if new_x >= width || new_y >= height || passability_matrix[(new_x, new_y)] == 0 {
+                continue;
+            }
+
+            let distance = distances[(x, y)] + passability_matrix[(new_x, new_y)];
+            if distance < distances[(new_x, new_y)] {
+                distances[(new_x, new_y)] = distance;
+                owners[(new_x, new_y)] = owner;
+                claim_lengths[(new_x, new_y)] = lengths[owner];
+                queue.push_back((new_x, new_y, owner));
+            } else if distances[(new_x, new_y)] == distance && owners[(new_x, new_y)] != owner {
+                if lengths[owner] > claim_lengths[(new_x, new_y)] {
+                    owners[(new_x, new_y)] = owner;
+                    claim_lengths[(new_x, new_y)] = lengths[owner];
+                    queue.push_back((new_x, new_y, owner));
+                } else if lengths[owner] == claim_lengths[(new_x, new_y)] {
+                    owners[(new_x, new_y)] = CONTESTED;
+                }
+            }
+        }
+    }
+
+    let mut territory = vec![0; heads.len()];
+    for x in 0..width {
+        for y in 0..height {
+            if passability_matrix[(x, y)] == 1 && owners[(x, y)] != CONTESTED {
+                territory[owners[(x, y)]] += 100;
+            } else if passability_matrix[(x, y)] > 1 && owners[(x, y)] != CONTESTED {
+                territory[owners[(x, y)]] += HAZARD_CELL_VALUE;
+            }
+        }
+    }
+
+    territory
+}
+
+// `voronoi` with whole-board bit operations, one ring of cells per step, for boards where every
+// free cell costs one step. A cell reached by several snakes in the same ring goes to the
+// longest of them, a tie for longest leaves it to nobody and nobody floods on from it.
+// Snakes without a head are ignored.
+pub(crate) fn flood_territory(board: &GameBoard, heads: &[Option<Coord>], lengths: &[i32]) -> Vec<i32> {
+    if board.layout.fits_u128() {
+        flood_territory_on::<u128>(board, heads, lengths)
+    } else {
+        flood_territory_on::<Bitboard>(board, heads, lengths)
+    }
+}
+
+#[allow(clippy::needless_range_loop)]
+fn flood_territory_on<B: Bits>(board: &GameBoard, heads: &[Option<Coord>], lengths: &[i32]) -> Vec<i32> {
+    let layout: Layout<B> = board.layout.with_bits();
+    let free = layout.all() & !B::from_bitboard(board.occupied());
+
+    let mut frontiers: Vec<B> = heads.iter()
+        .map(|head| head.filter(|head| board.in_bounds(*head))
+            .map(|head| B::single(layout.index(head)))
+            .unwrap_or_default())
+        .collect();
+    let mut reached = vec![B::default(); heads.len()];
+    let mut claimed = B::default();
+    let mut territory = vec![0; heads.len()];
+
+    loop {
+        let mut any = false;
+        for (frontier, reached) in frontiers.iter().zip(reached.iter_mut()) {
+            *reached = layout.neighbours(*frontier) & free & !claimed;
+            any |= !reached.is_empty();
+        }
+        if !any {
+            break;
+        }
+
+        for i in 0..heads.len() {
+            let mut rivals = B::default();
+            for j in 0..heads.len() {
+                if j != i && lengths[j] >= lengths[i] {
+                    rivals |= reached[j];
+                }
+            }
+
+            frontiers[i] = reached[i] & !rivals;
+            territory[i] += 100 * frontiers[i].count_ones() as i32;
+            claimed |= reached[i];
+        }
+    }
+
+    territory
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use crate::Battlesnake;
+    use crate::board::{Direction, GameMode, Ruleset};
+    use super::*;
+
+    fn snake(id: &str, x: i32, y: i32) -> Battlesnake {
+        Battlesnake {
+            id: id.to_string(),
+            name: id.to_string(),
+            health: 100,
+            body: vec![Coord { x, y }; 3],
+            head: Coord { x, y },
+            length: 3,
+            latency: String::new(),
+            shout: None,
+        }
+    }
+
+    // Plays random moves on a board and checks the bitboard flood against the grid voronoi
+    // after every turn, with snakes growing apart as they eat
+    fn check_flood(width: i32, height: i32, mode: GameMode, hazards: Vec<Coord>, hazard_damage_per_turn: i32) {
+        let ruleset = Ruleset { mode, food_spawn_chance: 25, minimum_food: 3, hazard_damage_per_turn };
+        let snakes = vec![
+            snake("a", 1, 1), snake("b", width - 2, 1), snake("c", 1, height - 2), snake("d", width - 2, height - 2),
+        ];
+        let mut board = GameBoard::new(width, height, vec![], snakes, hazards, ruleset);
+        assert!(unit_costs(&board));
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..80 {
+            let alive: Vec<usize> = (0..board.snakes.len()).filter(|snake| !board.is_eliminated(*snake)).collect();
+            if alive.len() < 2 {
+                break;
+            }
+
+            let heads: Vec<Option<Coord>> = alive.iter().map(|snake| Some(board.snakes[*snake].head())).collect();
+            let grid_heads: Vec<(usize, usize)> = heads.iter().flatten()
+                .map(|head| (head.x as usize, head.y as usize))
+                .collect();
+            let lengths: Vec<i32> = alive.iter().map(|snake| board.snakes[*snake].length).collect();
+
+            let passability_matrix: Grid<i32> = passability(&board);
+            let expected = voronoi::<Grid<i32>, Grid<usize>>(&passability_matrix, wrap_dimensions(&board), &grid_heads, &lengths);
+            assert_eq!(flood_territory(&board, &heads, &lengths), expected, "{:?}", board.to_board());
+
+            let moves: Vec<_> = alive.iter()
+                .map(|snake| {
+                    let legal = board.generate_legal_moves(*snake);
+                    (*snake, if legal.is_empty() { Direction::Up } else { legal[rng.gen_range(0..legal.len())] })
+                })
+                .collect();
+            board.step(&moves);
+            board.spawn_food(&mut rng);
+        }
+    }
+
+    // Plays random two snake games and checks `eval` against the grid voronoi's territory with
+    // the health and length terms on top
+    fn check_eval(width: i32, height: i32, mode: GameMode, seed: u64) {
+        let ruleset = Ruleset { mode, food_spawn_chance: 25, minimum_food: 3, hazard_damage_per_turn: 14 };
+        let snakes = vec![snake("a", 1, 1), snake("b", width - 2, height - 2)];
+        let mut board = GameBoard::new(width, height, vec![], snakes, vec![], ruleset);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for _ in 0..120 {
+            if board.is_eliminated(0) || board.is_eliminated(1) {
+                break;
+            }
+
+            for (snake, enemy) in [(0, 1), (1, 0)] {
+                let (us, them) = (&board.snakes[snake], &board.snakes[enemy]);
+                let heads = [(us.head().x as usize, us.head().y as usize), (them.head().x as usize, them.head().y as usize)];
+                let territory = voronoi::<Grid<i32>, Grid<usize>>(&passability(&board), wrap_dimensions(&board),
+                                                                  &heads, &[us.length, them.length]);
+                let mut expected = territory[0] - territory[1];
+                if !mode.constricts() {
+                    expected += health_term(us) + length_term(us, them.length);
+                }
+
+                assert_eq!(eval(&board, snake, enemy), expected, "{:?}", board.to_board());
+            }
+
+            let moves: Vec<_> = (0..2)
+                .map(|snake| {
+                    let legal = board.generate_legal_moves(snake);
+                    (snake, if legal.is_empty() { Direction::Up } else { legal[rng.gen_range(0..legal.len())] })
+                })
+                .collect();
+            board.step(&moves);
+            board.spawn_food(&mut rng);
+        }
+    }
+
+    #[test]
+    fn two_snake_eval_splits_territory_by_the_nearest_head() {
+        for seed in 0..8 {
+            check_eval(11, 11, GameMode::Standard, seed);
+            check_eval(7, 13, GameMode::Wrapped, seed);
+            check_eval(11, 11, GameMode::Constrictor, seed);
+        }
+    }
+
+    #[test]
+    fn flood_matches_voronoi_on_standard_boards() {
+        check_flood(11, 11, GameMode::Standard, vec![], 14);
+        check_flood(19, 19, GameMode::Standard, vec![], 14);
+    }
+
+    #[test]
+    fn flood_matches_voronoi_on_wrapped_boards() {
+        check_flood(11, 11, GameMode::Wrapped, vec![], 14);
+        check_flood(19, 19, GameMode::Wrapped, vec![], 14);
+    }
+
+    #[test]
+    fn flood_matches_voronoi_with_cheap_hazards() {
+        let hazards: Vec<Coord> = (0..11).flat_map(|y| vec![Coord { x: 0, y }, Coord { x: 5, y }]).collect();
+        check_flood(11, 11, GameMode::Standard, hazards, HAZARD_DAMAGE_PER_STEP - 1);
+    }
+
+    #[test]
+    fn hazards_count_in_full_until_they_cost_a_step() {
+        // Our side's edge is a hazard, nobody can be cut off behind it
+        let hazards: Vec<Coord> = (0..11).map(|y| Coord { x: 0, y }).collect();
+        let snakes = || vec![
+            snake("a", 1, 5),
+            snake("b", 9, 5),
+        ];
+        let with_damage = |hazard_damage_per_turn: i32| {
+            let ruleset = Ruleset { hazard_damage_per_turn, ..Ruleset::default() };
+            GameBoard::new(11, 11, vec![], snakes(), hazards.clone(), ruleset)
+        };
+        let clear = GameBoard::new(11, 11, vec![], snakes(), vec![], Ruleset::default());
+
+        for damage in 1..HAZARD_DAMAGE_PER_STEP {
+            assert_eq!(eval(&with_damage(damage), 0, 1), eval(&clear, 0, 1), "damage {}", damage);
+        }
+
+        // From here on each of our 11 hazard cells is worth less
+        let full = eval_on::<Grid<i32>>(&clear, 0, 1);
+        let discount = 11 * (100 - HAZARD_CELL_VALUE);
+        for damage in [HAZARD_DAMAGE_PER_STEP, 2 * HAZARD_DAMAGE_PER_STEP] {
+            assert_eq!(eval(&with_damage(damage), 0, 1), full - discount, "damage {}", damage);
+        }
+    }
+}
@@ -1,3 +1,14 @@
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::error;
+use serde::Serialize;
+use crate::config::EngineConfig;
+use crate::writer::BackgroundWriter;
+
 pub struct RoundInfo {
     pub(crate) snake_bfs: i32,
     pub(crate) enemy_bfs: i32,
@@ -9,4 +20,242 @@ pub struct RoundInfo {
 pub struct GameRecorder {
     pub(crate) rounds: Vec<RoundInfo>,
     pub(crate) ending: f32
-}
\ No newline at end of file
+}
+
+// How the rounds of finished games are written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingDataFormat {
+    // Not at all
+    Off,
+    // With a header line whenever a file is started
+    Csv,
+    // One JSON object per line
+    JsonLines,
+    // 24 bytes a round: the five features as little endian i32s, then the ending as an f32
+    Binary,
+}
+
+impl TrainingDataFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            TrainingDataFormat::Off => "",
+            TrainingDataFormat::Csv => "csv",
+            TrainingDataFormat::JsonLines => "jsonl",
+            TrainingDataFormat::Binary => "bin",
+        }
+    }
+}
+
+impl FromStr for TrainingDataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(TrainingDataFormat::Off),
+            "csv" => Ok(TrainingDataFormat::Csv),
+            "jsonl" => Ok(TrainingDataFormat::JsonLines),
+            "binary" => Ok(TrainingDataFormat::Binary),
+            _ => Err(format!("unknown training data format {}", s)),
+        }
+    }
+}
+
+// Which file the rounds of a game go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrainingDataFiles {
+    // One for every game, named after its id
+    PerGame,
+    // One for every day (UTC) games end on, named after the date
+    PerDay,
+}
+
+impl FromStr for TrainingDataFiles {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "game" => Ok(TrainingDataFiles::PerGame),
+            "day" => Ok(TrainingDataFiles::PerDay),
+            _ => Err(format!("unknown training data files {}", s)),
+        }
+    }
+}
+
+// One line of training data, the way every format but the binary one names its fields
+#[derive(Serialize)]
+struct Row {
+    snake_bfs: i32,
+    enemy_bfs: i32,
+    snake_length: i32,
+    enemy_length: i32,
+    health: i32,
+    ending: f32,
+}
+
+struct Export {
+    game_id: String,
+    recorder: GameRecorder,
+}
+
+// Writes the recorders of finished games on a thread of its own, so `/end` never waits on the
+// disk. A write that fails is logged and the game's rounds are lost, nothing else.
+pub struct TrainingDataWriter {
+    writer: BackgroundWriter<Export>,
+}
+
+impl TrainingDataWriter {
+    pub fn new(config: &EngineConfig) -> Self {
+        if config.training_data_format == TrainingDataFormat::Off {
+            return Self { writer: BackgroundWriter::off() };
+        }
+
+        let (dir, files, format) = (config.training_data_dir.clone(), config.training_data_files, config.training_data_format);
+        let writer = BackgroundWriter::start("training-data", move |export: Export| {
+            if let Err(e) = write_export(&dir, files, format, &export) {
+                error!("Couldn't write the training data of game {}: {}", export.game_id, e);
+            }
+        });
+
+        Self { writer }
+    }
+
+    pub fn submit(&self, game_id: &str, recorder: GameRecorder) {
+        if !self.writer.is_on() || recorder.rounds.is_empty() {
+            return;
+        }
+        if !self.writer.send(Export { game_id: game_id.to_string(), recorder }) {
+            error!("The training data writer is gone, dropping the rounds of game {}", game_id);
+        }
+    }
+}
+
+fn write_export(dir: &Path, files: TrainingDataFiles, format: TrainingDataFormat, export: &Export) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+
+    let name = match files {
+        TrainingDataFiles::PerGame => file_name(&export.game_id),
+        TrainingDataFiles::PerDay => today(),
+    };
+    let path: PathBuf = dir.join(format!("{}.{}", name, format.extension()));
+
+    let file = OpenOptions::new().append(true).create(true).open(&path)?;
+    let is_new = file.metadata()?.len() == 0;
+
+    let recorder = &export.recorder;
+    let rows = recorder.rounds.iter().map(|round| Row {
+        snake_bfs: round.snake_bfs,
+        enemy_bfs: round.enemy_bfs,
+        snake_length: round.snake_length,
+        enemy_length: round.enemy_length,
+        health: round.health,
+        ending: recorder.ending,
+    });
+
+    match format {
+        TrainingDataFormat::Off => {}
+        TrainingDataFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new().has_headers(is_new).from_writer(file);
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        TrainingDataFormat::JsonLines => {
+            let mut writer = BufWriter::new(file);
+            for row in rows {
+                serde_json::to_writer(&mut writer, &row)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        TrainingDataFormat::Binary => write_binary(file, rows)?,
+    }
+
+    Ok(())
+}
+
+fn write_binary(file: File, rows: impl Iterator<Item = Row>) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(file);
+    for row in rows {
+        for value in [row.snake_bfs, row.enemy_bfs, row.snake_length, row.enemy_length, row.health] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&row.ending.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+// Game ids come from the server, anything but letters, digits, `-` and `_` is kept out of paths
+pub fn file_name(game_id: &str) -> String {
+    game_id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+// YYYY-MM-DD in UTC
+fn today() -> String {
+    civil_date(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs() / 86400) as i64)
+}
+
+// The date `days` after 1970-01-01, the way the proleptic Gregorian calendar counts them
+fn civil_date(days: i64) -> String {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    fn recorder(rounds: i32) -> GameRecorder {
+        GameRecorder {
+            rounds: (0..rounds)
+                .map(|turn| RoundInfo { snake_bfs: turn, enemy_bfs: 0, snake_length: 3, enemy_length: 3, health: 100 })
+                .collect(),
+            ending: 1.0,
+        }
+    }
+
+    #[test]
+    fn days_since_the_epoch_are_civil_dates() {
+        assert_eq!(civil_date(0), "1970-01-01");
+        assert_eq!(civil_date(-1), "1969-12-31");
+        assert_eq!(civil_date(11016), "2000-02-29");
+        assert_eq!(civil_date(20088), "2024-12-31");
+    }
+
+    #[test]
+    fn appending_to_a_csv_file_keeps_one_header() {
+        let dir = std::env::temp_dir().join(format!("training-data-test-{}", process::id()));
+        let config = EngineConfig {
+            training_data_dir: dir.clone(),
+            training_data_files: TrainingDataFiles::PerGame,
+            training_data_format: TrainingDataFormat::Csv,
+            ..EngineConfig::default()
+        };
+
+        // Dropping the writer waits for both games to be written
+        let writer = TrainingDataWriter::new(&config);
+        writer.submit("game", recorder(2));
+        writer.submit("game", recorder(3));
+        drop(writer);
+
+        let written = fs::read_to_string(dir.join("game.csv")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let lines: Vec<&str> = written.lines().collect();
+        assert_eq!(lines.len(), 1 + 5);
+        assert_eq!(lines[0], "snake_bfs,enemy_bfs,snake_length,enemy_length,health,ending");
+        assert_eq!(lines.iter().filter(|line| line.starts_with("snake_bfs")).count(), 1);
+    }
+}
@@ -0,0 +1,92 @@
+use std::ops::{Index, IndexMut};
+
+// Boards up to this size fit in a `SmallGrid`
+pub const SMALL_GRID_SIZE: usize = 11;
+
+// Anything that stores one value per cell and is indexed with [(x, y)]. The evals are written
+// against this so the standard board gets a fixed-size grid and everything else a `Grid`.
+pub trait Cells<T: Copy>: Index<(usize, usize), Output = T> + IndexMut<(usize, usize)> {
+    fn filled(width: usize, height: usize, value: T) -> Self;
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+}
+
+// A width x height board of values in one allocation, for any board size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Copy> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl<T: Copy> Cells<T> for Grid<T> {
+    fn filled(width: usize, height: usize, value: T) -> Self {
+        Self::new(width, height, value)
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        &self.cells[x * self.height + y]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        &mut self.cells[x * self.height + y]
+    }
+}
+
+// A fixed 11x11 array, measurably faster than `Grid` for the evals that build a few of these
+// on every node. Smaller boards leave the cells past their edge at the fill value, so those
+// have to be filled with something that reads as a wall.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmallGrid<T>([[T; SMALL_GRID_SIZE]; SMALL_GRID_SIZE]);
+
+impl<T: Copy> Cells<T> for SmallGrid<T> {
+    fn filled(_width: usize, _height: usize, value: T) -> Self {
+        Self([[value; SMALL_GRID_SIZE]; SMALL_GRID_SIZE])
+    }
+
+    fn width(&self) -> usize {
+        SMALL_GRID_SIZE
+    }
+
+    fn height(&self) -> usize {
+        SMALL_GRID_SIZE
+    }
+}
+
+impl<T> Index<(usize, usize)> for SmallGrid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        &self.0[x][y]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for SmallGrid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        &mut self.0[x][y]
+    }
+}
@@ -0,0 +1,126 @@
+#[macro_use]
+extern crate rocket;
+
+use rocket::serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use crate::config::EngineConfig;
+use crate::game_recorder::{GameRecorder, RoundInfo};
+use crate::stats::GameStats;
+use crate::time_manager::LatencyTracker;
+use crate::transposition_table::TranspositionTable;
+
+// The engine and everything around it. The server and the tools in `src/bin` are thin
+// wrappers around `server`, `analyze`, `arena` and `sprt`.
+pub mod server;
+pub mod analyze;
+pub mod arena;
+pub mod sprt;
+
+mod logic;
+mod board;
+mod search;
+mod eval;
+mod transposition_table;
+mod game_recorder;
+mod config;
+mod time_manager;
+mod grid;
+mod bitboard;
+mod snake;
+mod zobrist;
+mod stats;
+mod metrics;
+mod replay;
+mod writer;
+mod cli;
+
+// API and Response Objects
+// See https://docs.battlesnake.com/api
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Game {
+    id: String,
+    ruleset: HashMap<String, Value>,
+    timeout: u32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Board {
+    height: i32,
+    width: i32,
+    food: Vec<Coord>,
+    snakes: Vec<Battlesnake>,
+    hazards: Vec<Coord>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Battlesnake {
+    id: String,
+    name: String,
+    health: i32,
+    body: Vec<Coord>,
+    head: Coord,
+    length: i32,
+    latency: String,
+    shout: Option<String>,
+}
+
+struct GameState {
+    game: Game,
+    turn: i32,
+    board: Board,
+    you: Battlesnake,
+    tt: TranspositionTable,
+    killers: [(Coord, Coord); 1000],
+    game_recorder: GameRecorder,
+    latency: LatencyTracker,
+    // Seeded in `logic::start`, anything random in the engine draws from it
+    rng: StdRng,
+    // Every search of the game so far, logged at `/end`
+    stats: GameStats,
+}
+
+impl GameState {
+    fn new(req: &JsonGameState, config: &EngineConfig) -> Self {
+        Self {
+            game: req.game.clone(),
+            turn: req.turn,
+            board: req.board.clone(),
+            you: req.you.clone(),
+            tt: TranspositionTable::new(config.tt_size_mb),
+            killers: [(Coord { x: -1, y: -1 }, Coord { x: -1, y: -1 }); 1000],
+            game_recorder: GameRecorder { rounds: vec![], ending: 0.5 },
+            latency: LatencyTracker::new(),
+            rng: StdRng::seed_from_u64(0),
+            stats: GameStats::default(),
+        }
+    }
+
+    // Roughly, the table is nearly all of it
+    fn memory_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.tt.memory_bytes()
+            + self.game_recorder.rounds.capacity() * std::mem::size_of::<RoundInfo>()
+            + self.board.snakes.iter().map(|snake| snake.body.capacity()).sum::<usize>() * std::mem::size_of::<Coord>()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Copy)]
+pub struct Coord {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct JsonGameState {
+    game: Game,
+    turn: i32,
+    board: Board,
+    you: Battlesnake,
+}
+
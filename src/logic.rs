@@ -10,65 +10,194 @@
 // To get you started we've included code to prevent your Battlesnake from moving backwards.
 // For more info see docs.battlesnake.com
 
+use std::panic;
+use std::time::Instant;
 use log::info;
-use rand::Rng;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
 use serde_json::{json, Value};
-use crate::{Battlesnake, Board, Game, GameState};
-use crate::board::{Direction, GameBoard};
+use crate::{Battlesnake, Board, Coord, Game, GameState, JsonGameState};
+use crate::board::{Direction, GameBoard, Ruleset};
+use crate::config::EngineConfig;
+use crate::metrics::{Metrics, Outcome};
 use crate::game_recorder::GameRecorder;
-use crate::search::think;
+use crate::search::{format_pv, think, SearchPool, SearchResult};
+use crate::stats::{GameStats, SearchStats};
+use crate::time_manager::TimeManager;
+use crate::transposition_table::TranspositionTable;
 
 pub fn info() -> Value {
     info!("INFO");
 
-    return json!({
+    json!({
         "apiversion": "1",
         "author": "JeffLegendPower",
         "color": "#888888", // TODO: Choose color
         "head": "default", // TODO: Choose head
         "tail": "default", // TODO: Choose tail
-    });
+    })
 }
 
 // start is called when your Battlesnake begins a game
-pub fn start(game: &mut GameState) {
+pub fn start(game: &mut GameState, config: &EngineConfig) {
 
-    // Populate the zobrist table
-    let mut rng = rand::thread_rng();
-    for _ in 0..(game.board.width as i32 * game.board.height as i32 * 2) {
-        game.zobrist_table.push(rng.gen());
-    }
-    for _ in 0..100 {
-        game.health_zobrist_table.push(rng.gen());
-    }
+    // Every game gets its own stream from the engine seed. That only makes a game repeat with
+    // SEARCH_THREADS=1 and a MAX_SEARCH_DEPTH the clock never cuts short, otherwise thread timing
+    // and the deadline decide how deep each move gets
+    game.rng = StdRng::seed_from_u64(config.seed ^ game_seed(&game.game.id));
+    info!("Game {} seeded from ENGINE_SEED={}", game.game.id, config.seed);
 
     info!("GAME START");
 }
 
+// FNV-1a of the game id, the same on every platform and every build
+pub fn game_seed(game_id: &str) -> u64 {
+    game_id.bytes().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
 // end is called when your Battlesnake finishes a game
-pub fn end(_game: &Game, _turn: &i32, _board: &Board, _you: &Battlesnake) {
+pub fn end(game: &Game, turn: &i32, board: &Board, you: &Battlesnake, stats: &GameStats, metrics: &Metrics) {
     info!("GAME OVER");
+    metrics.record_outcome(outcome(board, you));
+    info!("GAME STATS {}", json!({ "game": game.id, "turns": turn, "stats": stats.to_json() }));
+}
+
+// The board `/end` gets only has the snakes that are still alive
+fn outcome(board: &Board, you: &Battlesnake) -> Outcome {
+    if board.snakes.iter().any(|snake| snake.id == you.id) {
+        Outcome::Win
+    } else if board.snakes.is_empty() {
+        Outcome::Draw
+    } else {
+        Outcome::Loss
+    }
 }
 
 // move is called on every turn and returns your next move
 // Valid moves are "up", "down", "left", or "right"
 // See https://docs.battlesnake.com/api/example-move for available data
 // pub fn get_move(_game: &Game, turn: &i32, _board: &Board, you: &Battlesnake) -> Value {
-pub fn get_move(game: &mut GameState) -> Value {
-    let board = &game.board;
+// The response goes to the server, the search result is kept with the replay
+pub fn get_move(game: &mut GameState, config: &EngineConfig, pool: &SearchPool, metrics: &Metrics,
+                start_time: Instant) -> (Value, SearchResult) {
+    game.latency.observe(&game.you.latency);
+    let mut time_manager = TimeManager::new(start_time, game.game.timeout, game.latency.network_overhead_ms(), config);
 
-    let game_board: GameBoard = GameBoard::new(board.width, board.height, board.food.clone(), board.snakes.clone(), board.hazards.clone(),
-                                                   &game.zobrist_table, &game.health_zobrist_table);
-    let best_move = think(&mut game.game_recorder, game_board, game.you.clone(), &mut game.tt, &mut game.killers);
+    let game_board = game_board(&game.game, &game.board);
+    let (result, stats) = think(&mut game.game_recorder, game_board.clone(), game.you.clone(), &game.tt, &mut game.killers,
+                                &mut time_manager, pool, &mut game.rng, config);
+
+    if config.print_searches {
+        info!("SEARCH {} turn {}: {} snakes, {} threads, {} nodes, depth {}, score {}, move {}, hashfull {}, {}ms of {}ms{}",
+              game.game.id, game.turn, game.board.snakes.len(), stats.threads, stats.counters.nodes, result.depth,
+              result.score, result.best_move.as_str(), game.tt.hashfull(), stats.time.as_millis(),
+              time_manager.hard_limit().as_millis(), if stats.aborted { " (aborted)" } else { "" });
+        info!("PV {} turn {}: {}", game.game.id, game.turn, format_pv(&game_board, &result.pv));
+    }
 
-    let best_move_str = match best_move {
-        Direction::Up => "up",
-        Direction::Down => "down",
-        Direction::Left => "left",
-        Direction::Right => "right",
-        Direction::None => "up",
-    };
+    info!("SEARCH STATS {}", json!({ "game": game.game.id, "turn": game.turn, "stats": stats.to_json() }));
+    game.stats.record(&stats);
+    metrics.record_search(&stats);
+
+    // The search only comes back without a move when every move runs into something, any of them will do
+    let best_move_str = match result.best_move {
+        Direction::None => game_board.first_safe_move(game_board.snake_index(&game.you.id)).unwrap_or(Direction::Up),
+        best_move => best_move,
+    }.as_str();
 
     info!("MOVE {}: {}", game.turn, best_move_str);
-    return json!({ "move": best_move_str });
+    game.latency.record_response(start_time.elapsed());
+    (json!({ "move": best_move_str, "shout": shout(&result) }), result)
+}
+
+// The search's board for a position of the API
+pub fn game_board(game: &Game, board: &Board) -> GameBoard {
+    GameBoard::new(board.width, board.height, board.food.clone(), board.snakes.clone(), board.hazards.clone(),
+                   Ruleset::from_game(&game.ruleset))
+}
+
+// Boards the search can't hold get an answer without one
+pub fn searchable(board: &Board) -> Result<(), String> {
+    GameBoard::check_size(board.width, board.height, board.snakes.len())
+}
+
+// What we answer when the search can't: the first move that isn't risky, else the first that doesn't
+// run straight into something, or up if even working that out fails
+pub fn fallback_move(request: &JsonGameState) -> Value {
+    if searchable(&request.board).is_err() {
+        return json!({ "move": clear_move(request).as_str() });
+    }
+
+    let direction = panic::catch_unwind(|| {
+        let game_board = game_board(&request.game, &request.board);
+        game_board.first_safe_move(game_board.snake_index(&request.you.id))
+    }).ok().flatten().unwrap_or(Direction::Up);
+
+    json!({ "move": direction.as_str() })
+}
+
+// The first move that stays on the board and off every body, for boards too big for a `GameBoard`
+fn clear_move(request: &JsonGameState) -> Direction {
+    let board = &request.board;
+    [Direction::Up, Direction::Down, Direction::Left, Direction::Right].iter().copied()
+        .find(|direction| {
+            let next = request.you.head.step(*direction);
+            next.x >= 0 && next.x < board.width && next.y >= 0 && next.y < board.height
+                && !board.snakes.iter().any(|snake| snake.body.contains(&next))
+        })
+        .unwrap_or(Direction::Up)
+}
+
+// The server shows shouts to everyone watching, so this is the short version of the line we expect:
+// depth, score and the first letter of every move, cut to the 256 characters the server takes
+fn shout(result: &SearchResult) -> String {
+    let line: String = result.pv.iter()
+        .map(|pv_move| pv_move.direction.as_str()[..1].to_ascii_uppercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut shout = format!("d{} {}: {}", result.depth, result.score, line);
+    shout.truncate(256);
+    shout
+}
+
+// Searches a position the way `get_move` would, but on its own table and without touching any
+// running game, and explains the move it comes up with
+pub fn analyze(request: &JsonGameState, config: &EngineConfig, pool: &SearchPool, start_time: Instant) -> Value {
+    let board = &request.board;
+    if let Err(e) = searchable(board) {
+        return json!({ "error": e });
+    }
+
+    let mut time_manager = TimeManager::new(start_time, request.game.timeout, 0, config);
+    let (result, stats) = search_position(request, game_board(&request.game, board), &mut time_manager, pool, config);
+
+    // The board numbers snakes in the order the request lists them
+    let pv: Vec<Value> = result.pv.iter()
+        .map(|pv_move| {
+            let snake = &board.snakes[pv_move.snake];
+            json!({ "snake": snake.id, "name": snake.name, "move": pv_move.direction.as_str() })
+        })
+        .collect();
+
+    json!({
+        "move": result.best_move.as_str(),
+        "score": result.score,
+        "depth": result.depth,
+        "pv": pv,
+        "stats": stats.to_json(),
+    })
+}
+
+// Searches `board` for `request.you` on a table, killers and recorder of its own, so no running
+// game is touched
+pub fn search_position(request: &JsonGameState, board: GameBoard, time_manager: &mut TimeManager, pool: &SearchPool,
+                       config: &EngineConfig) -> (SearchResult, SearchStats) {
+    let transposition_table = TranspositionTable::new(config.tt_size_mb);
+    let mut killers = [(Coord { x: -1, y: -1 }, Coord { x: -1, y: -1 }); 1000];
+    let mut game_recorder = GameRecorder { rounds: vec![], ending: 0.5 };
+    let mut rng = StdRng::seed_from_u64(config.seed ^ game_seed(&request.game.id));
+
+    think(&mut game_recorder, board, request.you.clone(), &transposition_table, &mut killers,
+          time_manager, pool, &mut rng, config)
 }
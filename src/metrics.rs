@@ -0,0 +1,331 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use crate::stats::SearchStats;
+
+// Upper bounds of the histogram buckets, `+Inf` is added to each
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.2, 0.3, 0.4, 0.5, 0.75, 1.0, 2.5];
+const LOCK_WAIT_BUCKETS: &[f64] = &[0.000_01, 0.000_1, 0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0];
+const DEPTH_BUCKETS: &[f64] = &[2.0, 4.0, 6.0, 8.0, 10.0, 12.0, 14.0, 16.0, 20.0, 24.0, 32.0, 64.0];
+const NPS_BUCKETS: &[f64] = &[1e4, 5e4, 1e5, 2.5e5, 5e5, 1e6, 2.5e6, 5e6, 1e7];
+
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    fn new() -> Self {
+        Self(AtomicU64::new(0))
+    }
+
+    pub fn inc(&self) {
+        self.add(1);
+    }
+
+    pub fn add(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Cumulative like Prometheus wants them only when rendered, every observation lands in exactly
+// one bucket
+pub struct Histogram {
+    bounds: &'static [f64],
+    // One more than there are bounds, the last one is `+Inf`
+    buckets: Vec<AtomicU64>,
+    // The bits of an f64
+    sum: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
+            count: AtomicU64::new(0),
+        }
+    }
+
+    pub fn observe(&self, value: f64) {
+        let bucket = self.bounds.iter().position(|bound| value <= *bound).unwrap_or(self.bounds.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+
+        let mut sum = self.sum.load(Ordering::Relaxed);
+        while let Err(current) = self.sum.compare_exchange_weak(
+            sum, (f64::from_bits(sum) + value).to_bits(), Ordering::Relaxed, Ordering::Relaxed) {
+            sum = current;
+        }
+    }
+
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+}
+
+// How a game ended for us
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+// Things that went wrong and were recovered from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Incident {
+    // A search panicked, the move was answered with a fallback
+    SearchPanic,
+    // A lock was poisoned by an earlier panic
+    PoisonedLock,
+    // `/end` for a game we don't know
+    UnknownGame,
+}
+
+impl Incident {
+    pub fn label(self) -> &'static str {
+        match self {
+            Incident::SearchPanic => "search_panic",
+            Incident::PoisonedLock => "poisoned_lock",
+            Incident::UnknownGame => "unknown_game",
+        }
+    }
+}
+
+// Why a game was dropped before its `/end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eviction {
+    // No request for longer than the idle timeout
+    Idle,
+    // Made room for a new game, it was the least recently used
+    Capacity,
+}
+
+impl Eviction {
+    pub fn label(self) -> &'static str {
+        match self {
+            Eviction::Idle => "idle",
+            Eviction::Capacity => "capacity",
+        }
+    }
+}
+
+// Everything `/metrics` exports, shared by all requests
+pub struct Metrics {
+    pub(crate) move_latency: Histogram,
+    pub(crate) lock_wait: Histogram,
+    pub(crate) search_depth: Histogram,
+    pub(crate) search_nps: Histogram,
+    pub(crate) search_nodes: Counter,
+    pub(crate) searches_aborted: Counter,
+    pub(crate) moves: Counter,
+    // Moves we answered later than the game's timeout, as far as the server can tell
+    pub(crate) move_timeouts: Counter,
+    wins: Counter,
+    losses: Counter,
+    draws: Counter,
+    search_panics: Counter,
+    poisoned_locks: Counter,
+    unknown_games: Counter,
+    idle_evictions: Counter,
+    capacity_evictions: Counter,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            move_latency: Histogram::new(LATENCY_BUCKETS),
+            lock_wait: Histogram::new(LOCK_WAIT_BUCKETS),
+            search_depth: Histogram::new(DEPTH_BUCKETS),
+            search_nps: Histogram::new(NPS_BUCKETS),
+            search_nodes: Counter::new(),
+            searches_aborted: Counter::new(),
+            moves: Counter::new(),
+            move_timeouts: Counter::new(),
+            wins: Counter::new(),
+            losses: Counter::new(),
+            draws: Counter::new(),
+            search_panics: Counter::new(),
+            poisoned_locks: Counter::new(),
+            unknown_games: Counter::new(),
+            idle_evictions: Counter::new(),
+            capacity_evictions: Counter::new(),
+        }
+    }
+
+    pub fn record_search(&self, stats: &SearchStats) {
+        self.search_depth.observe(stats.depth as f64);
+        self.search_nps.observe(stats.nps() as f64);
+        self.search_nodes.add(stats.counters.nodes);
+        if stats.aborted {
+            self.searches_aborted.inc();
+        }
+    }
+
+    pub fn record_move(&self, latency: Duration, timeout_ms: u32) {
+        self.moves.inc();
+        self.move_latency.observe_duration(latency);
+        if latency.as_millis() >= timeout_ms as u128 {
+            self.move_timeouts.inc();
+        }
+    }
+
+    pub fn record_outcome(&self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => self.wins.inc(),
+            Outcome::Loss => self.losses.inc(),
+            Outcome::Draw => self.draws.inc(),
+        }
+    }
+
+    pub fn record_incident(&self, incident: Incident) {
+        match incident {
+            Incident::SearchPanic => self.search_panics.inc(),
+            Incident::PoisonedLock => self.poisoned_locks.inc(),
+            Incident::UnknownGame => self.unknown_games.inc(),
+        }
+    }
+
+    pub fn record_eviction(&self, eviction: Eviction) {
+        match eviction {
+            Eviction::Idle => self.idle_evictions.inc(),
+            Eviction::Capacity => self.capacity_evictions.inc(),
+        }
+    }
+
+    // The Prometheus text format. Active games and their memory are counted by whoever holds the
+    // games.
+    pub fn render(&self, active_games: usize, games_memory_bytes: u64) -> String {
+        let mut out = String::new();
+
+        histogram(&mut out, "battlesnake_move_duration_seconds",
+                  "Time from receiving a move request to answering it", &self.move_latency);
+        histogram(&mut out, "battlesnake_state_lock_wait_seconds",
+                  "Time spent waiting for the game state lock", &self.lock_wait);
+        histogram(&mut out, "battlesnake_search_depth",
+                  "Deepest completed iteration of every search", &self.search_depth);
+        histogram(&mut out, "battlesnake_search_nodes_per_second",
+                  "Nodes per second of every search, all threads together", &self.search_nps);
+        counter(&mut out, "battlesnake_search_nodes_total", "Nodes searched", &self.search_nodes);
+        counter(&mut out, "battlesnake_searches_aborted_total",
+                "Searches cut off by the hard deadline", &self.searches_aborted);
+        counter(&mut out, "battlesnake_moves_total", "Move requests answered", &self.moves);
+        counter(&mut out, "battlesnake_move_timeouts_total",
+                "Moves answered later than the game's timeout", &self.move_timeouts);
+
+        writeln!(out, "# HELP battlesnake_active_games Games currently tracked").unwrap();
+        writeln!(out, "# TYPE battlesnake_active_games gauge").unwrap();
+        writeln!(out, "battlesnake_active_games {}", active_games).unwrap();
+
+        writeln!(out, "# HELP battlesnake_games_memory_bytes Estimated memory held by the games currently tracked").unwrap();
+        writeln!(out, "# TYPE battlesnake_games_memory_bytes gauge").unwrap();
+        writeln!(out, "battlesnake_games_memory_bytes {}", games_memory_bytes).unwrap();
+
+        writeln!(out, "# HELP battlesnake_games_evicted_total Games dropped before their /end by reason").unwrap();
+        writeln!(out, "# TYPE battlesnake_games_evicted_total counter").unwrap();
+        for (eviction, counter) in [(Eviction::Idle, &self.idle_evictions), (Eviction::Capacity, &self.capacity_evictions)] {
+            writeln!(out, "battlesnake_games_evicted_total{{reason=\"{}\"}} {}", eviction.label(), counter.get()).unwrap();
+        }
+
+        writeln!(out, "# HELP battlesnake_games_total Finished games by outcome").unwrap();
+        writeln!(out, "# TYPE battlesnake_games_total counter").unwrap();
+        for (outcome, counter) in [("win", &self.wins), ("loss", &self.losses), ("draw", &self.draws)] {
+            writeln!(out, "battlesnake_games_total{{outcome=\"{}\"}} {}", outcome, counter.get()).unwrap();
+        }
+
+        writeln!(out, "# HELP battlesnake_incidents_total Failures recovered from by kind").unwrap();
+        writeln!(out, "# TYPE battlesnake_incidents_total counter").unwrap();
+        for (incident, counter) in [(Incident::SearchPanic, &self.search_panics),
+                                    (Incident::PoisonedLock, &self.poisoned_locks),
+                                    (Incident::UnknownGame, &self.unknown_games)] {
+            writeln!(out, "battlesnake_incidents_total{{kind=\"{}\"}} {}", incident.label(), counter.get()).unwrap();
+        }
+
+        out
+    }
+}
+
+fn counter(out: &mut String, name: &str, help: &str, counter: &Counter) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} counter", name).unwrap();
+    writeln!(out, "{} {}", name, counter.get()).unwrap();
+}
+
+fn histogram(out: &mut String, name: &str, help: &str, histogram: &Histogram) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} histogram", name).unwrap();
+
+    let mut cumulative = 0;
+    for (bound, bucket) in histogram.bounds.iter().zip(histogram.buckets.iter()) {
+        cumulative += bucket.load(Ordering::Relaxed);
+        writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative).unwrap();
+    }
+    cumulative += histogram.buckets[histogram.bounds.len()].load(Ordering::Relaxed);
+    writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, cumulative).unwrap();
+
+    writeln!(out, "{}_sum {}", name, f64::from_bits(histogram.sum.load(Ordering::Relaxed))).unwrap();
+    writeln!(out, "{}_count {}", name, histogram.count.load(Ordering::Relaxed)).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histograms_render_cumulative_buckets() {
+        let latency = Histogram::new(&[0.1, 0.5]);
+        for value in [0.05, 0.1, 0.3, 2.0] {
+            latency.observe(value);
+        }
+
+        let mut out = String::new();
+        histogram(&mut out, "latency", "Move latency", &latency);
+
+        assert_eq!(out, "\
+# HELP latency Move latency
+# TYPE latency histogram
+latency_bucket{le=\"0.1\"} 2
+latency_bucket{le=\"0.5\"} 3
+latency_bucket{le=\"+Inf\"} 4
+latency_sum 2.45
+latency_count 4
+");
+    }
+
+    #[test]
+    fn empty_histograms_still_have_every_bucket() {
+        let mut out = String::new();
+        histogram(&mut out, "depth", "Depth", &Histogram::new(&[2.0]));
+
+        assert!(out.contains("depth_bucket{le=\"2\"} 0\n"));
+        assert!(out.contains("depth_bucket{le=\"+Inf\"} 0\n"));
+        assert!(out.contains("depth_sum 0\ndepth_count 0\n"));
+    }
+
+    #[test]
+    fn outcomes_and_incidents_are_labelled() {
+        let metrics = Metrics::new();
+        metrics.record_outcome(Outcome::Win);
+        metrics.record_outcome(Outcome::Win);
+        metrics.record_outcome(Outcome::Draw);
+        metrics.record_incident(Incident::PoisonedLock);
+        metrics.record_eviction(Eviction::Capacity);
+        metrics.record_move(Duration::from_millis(600), 500);
+
+        let out = metrics.render(3, 1024);
+
+        assert!(out.contains("battlesnake_games_total{outcome=\"win\"} 2\n"));
+        assert!(out.contains("battlesnake_games_total{outcome=\"loss\"} 0\n"));
+        assert!(out.contains("battlesnake_games_total{outcome=\"draw\"} 1\n"));
+        assert!(out.contains("battlesnake_incidents_total{kind=\"poisoned_lock\"} 1\n"));
+        assert!(out.contains("battlesnake_games_evicted_total{reason=\"capacity\"} 1\n"));
+        assert!(out.contains("battlesnake_moves_total 1\n"));
+        assert!(out.contains("battlesnake_move_timeouts_total 1\n"));
+        assert!(out.contains("battlesnake_active_games 3\n"));
+        assert!(out.contains("battlesnake_games_memory_bytes 1024\n"));
+    }
+}
@@ -0,0 +1,271 @@
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use log::error;
+use serde::{Deserialize, Serialize};
+use crate::{Board, JsonGameState};
+use crate::board::GameBoard;
+use crate::config::EngineConfig;
+use crate::game_recorder::file_name;
+use crate::logic::game_board;
+use crate::search::SearchResult;
+use crate::writer::BackgroundWriter;
+
+// One request of a game as it came in, one JSON object per line of the game's replay file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "lowercase")]
+pub enum ReplayEvent {
+    Start { request: JsonGameState },
+    Move { request: JsonGameState, decision: Decision },
+    End { request: JsonGameState },
+}
+
+impl ReplayEvent {
+    pub fn request(&self) -> &JsonGameState {
+        match self {
+            ReplayEvent::Start { request } | ReplayEvent::Move { request, .. } | ReplayEvent::End { request } => request,
+        }
+    }
+}
+
+// What we answered a move with and why. Score and depth are missing when the search failed and
+// the move is a fallback.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Decision {
+    #[serde(rename = "move")]
+    pub(crate) direction: String,
+    pub(crate) score: Option<i32>,
+    pub(crate) depth: Option<i32>,
+    pub(crate) pv: Vec<PvStep>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PvStep {
+    // The snake's id
+    pub(crate) snake: String,
+    #[serde(rename = "move")]
+    pub(crate) direction: String,
+}
+
+impl Decision {
+    // `board` is the one the search ran on, the PV numbers its snakes the same way
+    pub fn searched(direction: &str, result: &SearchResult, board: &Board) -> Self {
+        Self {
+            direction: direction.to_string(),
+            score: Some(result.score),
+            depth: Some(result.depth),
+            pv: result.pv.iter()
+                .map(|pv_move| PvStep {
+                    snake: board.snakes[pv_move.snake].id.clone(),
+                    direction: pv_move.direction.as_str().to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn fallback(direction: &str) -> Self {
+        Self { direction: direction.to_string(), score: None, depth: None, pv: Vec::new() }
+    }
+}
+
+struct Record {
+    game_id: String,
+    event: ReplayEvent,
+}
+
+// Appends events to `<dir>/<game id>.jsonl` on a thread of its own, like the training data. A
+// write that fails is logged and the event is lost.
+pub struct ReplayWriter {
+    writer: BackgroundWriter<Record>,
+}
+
+impl ReplayWriter {
+    pub fn new(config: &EngineConfig) -> Self {
+        if !config.record_replays {
+            return Self { writer: BackgroundWriter::off() };
+        }
+
+        let dir = config.replay_dir.clone();
+        let writer = BackgroundWriter::start("replays", move |record: Record| {
+            if let Err(e) = append(&dir, &record.game_id, &record.event) {
+                error!("Couldn't record a replay event of game {}: {}", record.game_id, e);
+            }
+        });
+
+        Self { writer }
+    }
+
+    pub fn record(&self, game_id: &str, event: ReplayEvent) {
+        if !self.writer.send(Record { game_id: game_id.to_string(), event }) {
+            error!("The replay writer is gone, dropping an event of game {}", game_id);
+        }
+    }
+}
+
+pub fn replay_path(dir: &Path, game_id: &str) -> PathBuf {
+    dir.join(format!("{}.jsonl", file_name(game_id)))
+}
+
+// Adds `event` to the replay of `game_id` right away, `ReplayWriter` does it on its own thread
+pub fn append(dir: &Path, game_id: &str, event: &ReplayEvent) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+
+    let mut line = serde_json::to_vec(event)?;
+    line.push(b'\n');
+
+    // One write per event, so a crash never leaves half a line behind
+    let mut file = OpenOptions::new().append(true).create(true).open(replay_path(dir, game_id))?;
+    file.write_all(&line)?;
+    Ok(())
+}
+
+// A recorded game read back
+pub struct Replay {
+    pub(crate) events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    // `source` is what errors call the text, a file name usually
+    pub fn parse(text: &str, source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut events = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str(line)
+                .map_err(|e| format!("{} line {}: {}", source, i + 1, e))?;
+            events.push(event);
+        }
+        Ok(Self { events })
+    }
+
+    // Every turn we were asked to move on, in order
+    pub fn turns(&self) -> impl Iterator<Item = i32> + '_ {
+        self.moves().map(|(request, _)| request.turn)
+    }
+
+    pub fn moves(&self) -> impl Iterator<Item = (&JsonGameState, &Decision)> {
+        self.events.iter().filter_map(|event| match event {
+            ReplayEvent::Move { request, decision } => Some((request, decision)),
+            _ => None,
+        })
+    }
+
+    // The position of `turn`, from its move request or, for the first and the last turn, from
+    // the `/start` or `/end` request
+    pub fn request(&self, turn: i32) -> Option<&JsonGameState> {
+        self.moves().map(|(request, _)| request).find(|request| request.turn == turn)
+            .or_else(|| self.events.iter().map(ReplayEvent::request).find(|request| request.turn == turn))
+    }
+
+    pub fn decision(&self, turn: i32) -> Option<&Decision> {
+        self.moves().find(|(request, _)| request.turn == turn).map(|(_, decision)| decision)
+    }
+
+    pub fn board(&self, turn: i32) -> Option<GameBoard> {
+        self.request(turn).map(|request| game_board(&request.game, &request.board))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+    use serde_json::json;
+    use crate::Coord;
+    use super::*;
+
+    // A two snake position on `turn`, with our head `turn` cells up from where it started
+    fn request(turn: i32) -> JsonGameState {
+        let you = json!({
+            "id": "you", "name": "you", "health": 90, "latency": "", "length": 3,
+            "head": { "x": 1, "y": 1 + turn },
+            "body": [{ "x": 1, "y": 1 + turn }, { "x": 1, "y": turn }, { "x": 1, "y": turn }],
+        });
+        serde_json::from_value(json!({
+            "game": { "id": "game-1", "ruleset": { "name": "standard" }, "timeout": 500 },
+            "turn": turn,
+            "you": you,
+            "board": {
+                "width": 11, "height": 11, "food": [{ "x": 5, "y": 5 }], "hazards": [],
+                "snakes": [you, {
+                    "id": "them", "name": "them", "health": 90, "latency": "", "length": 3,
+                    "head": { "x": 9, "y": 9 },
+                    "body": [{ "x": 9, "y": 9 }, { "x": 9, "y": 10 }, { "x": 9, "y": 10 }],
+                }],
+            },
+        })).unwrap()
+    }
+
+    fn decision(direction: &str, score: i32) -> Decision {
+        Decision {
+            direction: direction.to_string(),
+            score: Some(score),
+            depth: Some(4),
+            pv: vec![PvStep { snake: "you".to_string(), direction: direction.to_string() }],
+        }
+    }
+
+    fn game() -> Vec<ReplayEvent> {
+        vec![
+            ReplayEvent::Start { request: request(0) },
+            ReplayEvent::Move { request: request(0), decision: decision("up", 10) },
+            ReplayEvent::Move { request: request(1), decision: decision("up", 20) },
+            ReplayEvent::Move { request: request(2), decision: Decision::fallback("left") },
+            ReplayEvent::End { request: request(3) },
+        ]
+    }
+
+    fn text(events: &[ReplayEvent]) -> String {
+        events.iter().map(|event| serde_json::to_string(event).unwrap() + "\n").collect()
+    }
+
+    #[test]
+    fn parse_reads_every_event_and_skips_blank_lines() {
+        let replay = Replay::parse(&format!("\n{}\n", text(&game())), "game.jsonl").unwrap();
+
+        assert_eq!(replay.events.len(), 5);
+        assert_eq!(replay.turns().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(replay.decision(1).and_then(|decision| decision.score), Some(20));
+        assert_eq!(replay.decision(2).and_then(|decision| decision.score), None);
+    }
+
+    #[test]
+    fn parse_errors_name_the_line() {
+        let broken = text(&game()[..2]) + "{\"event\": \"move\"}\n";
+
+        let error = Replay::parse(&broken, "game.jsonl").err().unwrap().to_string();
+        assert!(error.starts_with("game.jsonl line 3:"), "{}", error);
+    }
+
+    #[test]
+    fn turns_come_from_moves_then_start_and_end() {
+        let replay = Replay::parse(&text(&game()), "game.jsonl").unwrap();
+
+        assert_eq!(replay.request(1).map(|request| request.turn), Some(1));
+        // The last turn was never moved on, only `/end` has it
+        assert_eq!(replay.request(3).map(|request| request.you.head), Some(Coord { x: 1, y: 4 }));
+        assert!(replay.request(4).is_none());
+
+        let board = replay.board(2).unwrap();
+        assert_eq!(board.snakes[board.snake_index("you")].head(), Coord { x: 1, y: 3 });
+        assert_eq!(board.snakes[board.snake_index("them")].head(), Coord { x: 9, y: 9 });
+        assert!(board.is_food(Coord { x: 5, y: 5 }));
+    }
+
+    #[test]
+    fn appended_events_read_back_the_same() {
+        let dir = std::env::temp_dir().join(format!("replay-test-{}", process::id()));
+        for event in game() {
+            append(&dir, "game/1", &event).unwrap();
+        }
+
+        let written = fs::read_to_string(replay_path(&dir, "game/1")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(written, text(&game()));
+        let replay = Replay::parse(&written, "game.jsonl").unwrap();
+        assert_eq!(replay.moves().map(|(_, decision)| decision.direction.as_str()).collect::<Vec<_>>(),
+                   vec!["up", "up", "left"]);
+    }
+}
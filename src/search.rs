@@ -1,122 +1,437 @@
-use std::cell::RefCell;
-use std::cmp::Ordering;
-use std::fs::OpenOptions;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::time::Instant;
-use std::io::Write;
-use std::rc::Rc;
+use log::debug;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
 use crate::{Battlesnake, Coord};
-use crate::board::{CellContent, Direction, GameBoard};
-use crate::eval::{bfs, eval};
+use crate::board::{Direction, GameBoard};
+use crate::config::EngineConfig;
+use crate::eval::{bfs, eval, eval_multi, passability, wrap_dimensions};
+use crate::grid::Grid;
 use crate::game_recorder::{GameRecorder, RoundInfo};
-use crate::transposition_table::TTEntry;
+use crate::stats::{IterationStats, SearchCounters, SearchStats};
+use crate::time_manager::TimeManager;
+use crate::transposition_table::{TTEntry, TranspositionTable, EXACT, LOWER, UPPER};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    // Snakes take turns, each one seeing the moves of everyone before it
+    Sequential,
+    // Every ply is one joint move of all snakes, resolved together like the server does
+    Simultaneous,
+}
+
+impl FromStr for SearchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sequential" => Ok(SearchMode::Sequential),
+            "simultaneous" => Ok(SearchMode::Simultaneous),
+            _ => Err(format!("unknown search mode {}", s)),
+        }
+    }
+}
+
+// How a joint move node turns the scores of all move combinations into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimultaneousBackup {
+    // Assume the enemies always find the best reply to our move
+    Pessimistic,
+    // Solve the node as a matrix game and play the mixed strategy's value
+    Mixed,
+}
+
+impl FromStr for SimultaneousBackup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pessimistic" => Ok(SimultaneousBackup::Pessimistic),
+            "mixed" => Ok(SimultaneousBackup::Mixed),
+            _ => Err(format!("unknown simultaneous backup {}", s)),
+        }
+    }
+}
+
+// How many nodes are searched between two looks at the clock, must be a power of two
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+// One move of a principal variation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PvMove {
+    pub(crate) snake: usize,
+    pub(crate) direction: Direction,
+}
+
+// A move of ours from the root and what the last completed iteration thought of it. Alpha-beta
+// only proves the other moves are no better than the best one, their scores are upper bounds
+// unless `exact` says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RootMove {
+    pub(crate) direction: Direction,
+    pub(crate) score: i32,
+    pub(crate) exact: bool,
+}
+
+// What a search settled on as of its last completed iteration
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub(crate) best_move: Direction,
+    pub(crate) score: i32,
+    pub(crate) depth: i32,
+    // The line the search expects from the root, every snake's moves in the order it searched
+    // them: each ply on its own in sequential mode, all snakes of a joint move (us first) in
+    // simultaneous mode
+    pub(crate) pv: Vec<PvMove>,
+    // Every move we searched at the root, in the order they were searched
+    pub(crate) root_moves: Vec<RootMove>,
+}
+
+// Everything minimax needs that outlives a single node. Every search thread has its own, only
+// the transposition table and the stop flag are shared.
+pub struct SearchContext<'a> {
+    pub(crate) transposition_table: &'a TranspositionTable,
+    pub(crate) killers: &'a mut [(Coord, Coord); 1000],
+    // Indexed by `history_index`
+    pub(crate) history: Vec<i32>,
+    // The hard limit, the running iteration is aborted past it
+    pub(crate) deadline: Instant,
+    // Raised once the main thread has its answer, helper threads stop with it
+    pub(crate) stop: &'a AtomicBool,
+    pub(crate) past_moves: Vec<Direction>,
+    // Opponents that ran out of moves somewhere up the current line, only used with 3+ snakes
+    pub(crate) eliminated: Vec<bool>,
+    pub(crate) counters: SearchCounters,
+    // Every completed iteration, in order
+    pub(crate) iterations: Vec<IterationStats>,
+    // Set once the hard deadline passes, every node unwinds as soon as it sees it
+    pub(crate) aborted: bool,
+    // Only helper threads have one, they break ties in move ordering with it so they don't all
+    // search the same tree
+    pub(crate) rng: Option<StdRng>,
+    // Triangular PV table, `pv[ply]` is the best line found from the node at `ply` on. Every node
+    // clears its row first thing and builds it from its best move and the row below, so a line
+    // ends early wherever it ran into a transposition table cutoff.
+    pub(crate) pv: Vec<Vec<PvMove>>,
+    // The root moves of the running iteration
+    pub(crate) root_moves: Vec<RootMove>,
+}
+
+impl<'a> SearchContext<'a> {
+    fn new(board: &GameBoard, players: &[usize], transposition_table: &'a TranspositionTable,
+           killers: &'a mut [(Coord, Coord); 1000], deadline: Instant, stop: &'a AtomicBool) -> Self {
+        Self {
+            transposition_table,
+            killers,
+            history: vec![0; (board.width * board.height * 4) as usize],
+            deadline,
+            stop,
+            past_moves: Vec::new(),
+            eliminated: vec![false; players.len()],
+            counters: SearchCounters::default(),
+            iterations: Vec::new(),
+            aborted: false,
+            rng: None,
+            pv: Vec::new(),
+            root_moves: Vec::new(),
+        }
+    }
+
+    // Also makes sure the row below exists, so children never have to
+    fn clear_pv(&mut self, ply: i32) {
+        let ply = ply as usize;
+        if self.pv.len() < ply + 2 {
+            self.pv.resize_with(ply + 2, Vec::new);
+        }
+        self.pv[ply].clear();
+    }
+
+    // `pv[ply]` becomes `moves` followed by the line below them
+    fn update_pv(&mut self, ply: i32, moves: impl IntoIterator<Item = PvMove>) {
+        let (rows, below) = self.pv.split_at_mut(ply as usize + 1);
+        let row = &mut rows[ply as usize];
+        row.clear();
+        row.extend(moves);
+        row.extend_from_slice(&below[0]);
+    }
 
+    fn out_of_time(&mut self) -> bool {
+        if !self.aborted && self.counters.nodes & (TIME_CHECK_INTERVAL - 1) == 0
+            && (self.stop.load(Ordering::Relaxed) || Instant::now() >= self.deadline) {
+            self.aborted = true;
+        }
+        self.aborted
+    }
+
+    // The player after `to_move` that is still in the game. Index 0 (us) is never skipped,
+    // if we are out the line has already ended.
+    fn next_player(&self, to_move: usize) -> usize {
+        let mut next = (to_move + 1) % self.eliminated.len();
+        while self.eliminated[next] {
+            next = (next + 1) % self.eliminated.len();
+        }
+        next
+    }
+}
+
+// Helper threads for every search that runs at the same time. A search borrows up to
+// `threads - 1` of them for as long as it runs, never more than are idle and never more than
+// its share while other searches run too, so no helper job ever waits behind another search's.
+pub struct SearchPool {
+    pool: ThreadPool,
+    helpers: usize,
+    idle: AtomicUsize,
+    searches: AtomicUsize,
+}
+
+impl SearchPool {
+    fn lend(&self, wanted: usize) -> LentHelpers<'_> {
+        let searches = self.searches.fetch_add(1, Ordering::AcqRel) + 1;
+        let wanted = wanted.min(self.helpers.div_ceil(searches));
+
+        let mut idle = self.idle.load(Ordering::Acquire);
+        loop {
+            let count = wanted.min(idle);
+            match self.idle.compare_exchange_weak(idle, idle - count, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return LentHelpers { pool: self, count },
+                Err(now) => idle = now,
+            }
+        }
+    }
+}
+
+// Helpers a search has borrowed, back with the pool when it's dropped, even by a panic
+struct LentHelpers<'a> {
+    pool: &'a SearchPool,
+    count: usize,
+}
+
+impl Drop for LentHelpers<'_> {
+    fn drop(&mut self) {
+        self.pool.idle.fetch_add(self.count, Ordering::AcqRel);
+        self.pool.searches.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+// A pool for searches of `threads` threads, the calling thread and `threads - 1` helpers
+pub fn search_pool(threads: usize) -> Result<SearchPool, ThreadPoolBuildError> {
+    let helpers = threads.saturating_sub(1);
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(helpers.max(1))
+        .thread_name(|i| format!("search-{}", i))
+        .build()?;
+
+    Ok(SearchPool { pool, helpers, idle: AtomicUsize::new(helpers), searches: AtomicUsize::new(0) })
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn think(
     game_recorder: &mut GameRecorder,
-    mut board: GameBoard, snake: Battlesnake, transposition_table: &mut Vec<TTEntry>, killers: &mut [(Coord, Coord); 1000]
-) -> Direction {
-    let nearest_enemy = board.snakes.iter().filter(|s| s.borrow().id != snake.id).min_by_key(|s| {
-        let head = &s.borrow().head;
+    mut board: GameBoard, snake: Battlesnake, transposition_table: &TranspositionTable, killers: &mut [(Coord, Coord); 1000],
+    time_manager: &mut TimeManager, pool: &SearchPool, rng: &mut StdRng, config: &EngineConfig,
+) -> (SearchResult, SearchStats) {
+    let us = board.snake_index(&snake.id);
+    let mut enemies: Vec<usize> = (0..board.snakes.len()).filter(|i| *i != us).collect();
+    enemies.sort_by_key(|i| {
+        let head = board.snakes[*i].head();
         let snake_head = &snake.head;
-        return (head.x - snake_head.x).abs() + (head.y - snake_head.y).abs();
+        (head.x - snake_head.x).abs() + (head.y - snake_head.y).abs()
     });
 
-    let ref_snake = board.get_snake(&snake.id);
+    // Whatever happens with the clock, never answer with a move we already know is illegal
+    let best_move = board.first_safe_move(us).unwrap_or(Direction::None);
 
-    if nearest_enemy.is_none() {
-        let possible_moves = board.generate_possible_moves(ref_snake.clone());
-        if possible_moves.len() > 0 {
-            return possible_moves[0]
-        }
-        return Direction::None;
+    if enemies.is_empty() {
+        let result = SearchResult {
+            best_move,
+            score: 0,
+            depth: 0,
+            pv: Vec::new(),
+            root_moves: Vec::new(),
+        };
+        return (result, SearchStats { threads: 1, time: time_manager.elapsed(), ..SearchStats::default() });
     }
 
-    let mut nodes_searched = 0;
-    let mut best_move = Direction::None;
+    // We always move first, then everyone else from the nearest enemy outwards.
+    // With one enemy this is plain negamax, with more it's a paranoid search where every
+    // enemy is assumed to be working together against us.
+    let mut players = vec![us];
+    players.extend(enemies);
+    let enemy = players[1];
+
+    // println!("Eval: {}", eval(&board, enemy, us));
+
+    let stop = AtomicBool::new(false);
+    let helper_counters = Mutex::new(SearchCounters::default());
+    let deadline = time_manager.hard_deadline();
+    let helper_killers = *killers;
+    let helper_seeds: Vec<u64> = (1..config.threads).map(|_| rng.gen()).collect();
+    transposition_table.new_search();
+
+    let mut ctx = SearchContext::new(&board, &players, transposition_table, killers, deadline, &stop);
+
+    // println!("Before Endgame: {}", before_endgame(&board, us, enemy));
+
+    // Lazy SMP: the helpers search the same root as the main thread and only help through what
+    // they leave in the transposition table. Every other helper starts a depth ahead so they
+    // don't all walk the same tree in lockstep. The answer is always the main thread's, which is
+    // the calling thread. The helpers are stopped with it and were idle when they were lent, so
+    // the wait for them at the end is never behind another search's.
+    let helpers = pool.lend(config.threads - 1);
+    let mut result = pool.pool.in_place_scope(|scope| {
+        for (helper, seed) in (1..=helpers.count).zip(helper_seeds) {
+            let mut board = board.clone();
+            let mut killers = helper_killers;
+            let (players, stop, helper_counters) = (&players, &stop, &helper_counters);
+
+            scope.spawn(move |_| {
+                let mut ctx = SearchContext::new(&board, players, transposition_table, &mut killers, deadline, stop);
+                ctx.rng = Some(StdRng::seed_from_u64(seed));
+                iterate(&mut ctx, &mut board, players, 1 + helper as i32 % 2, None, config);
+                helper_counters.lock().unwrap().add(&ctx.counters);
+            });
+        }
 
-    let start_time = Instant::now();
+        let result = iterate(&mut ctx, &mut board, &players, 1, Some(&mut *time_manager), config);
+        stop.store(true, Ordering::Relaxed);
+        result
+    });
+    let threads = 1 + helpers.count;
+    drop(helpers);
 
-    let enemy_borrow = nearest_enemy.unwrap().borrow();
-    let enemy_id = enemy_borrow.id.clone();
+    if result.best_move == Direction::None {
+        result.best_move = best_move;
+    }
 
-    // println!("Eval: {}", eval(&board, board.get_snake(&enemy_id).clone(), ref_snake.clone(), false));
-    drop(enemy_borrow);
+    let mut counters = helper_counters.into_inner().unwrap();
+    counters.add(&ctx.counters);
+    let stats = SearchStats {
+        counters,
+        threads,
+        depth: result.depth,
+        time: time_manager.elapsed(),
+        aborted: ctx.aborted,
+        iterations: std::mem::take(&mut ctx.iterations),
+    };
 
-    // let mut depth = 1;
-    let mut depth = 0;
-    let mut score = 0;
-    let mut path: Vec<Direction> = Vec::new();
+    // The training features are ours against one enemy, they mean nothing with more snakes left
+    if board.snakes.len() == 2 {
+        record_round(result.score, &board, us, enemy, game_recorder);
+    }
 
-    let mut singular = 0;
-    let mut not_singular = 0;
+    (result, stats)
+}
 
-    let mut history = [[[[0; 11]; 11]; 11]; 11];
+// The line as "name:move" pairs
+pub fn format_pv(board: &GameBoard, pv: &[PvMove]) -> String {
+    pv.iter()
+        .map(|pv_move| format!("{}:{}", board.names[pv_move.snake], pv_move.direction.as_str()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
 
-    // println!("Before Endgame: {}", before_endgame(&board, ref_snake.clone(), board.get_snake(&enemy_id).clone()));
+// Iterative deepening from `first_depth` on, returning what the last completed depth found. Only
+// the main thread has a time manager, helpers go on until they are stopped.
+fn iterate(ctx: &mut SearchContext, board: &mut GameBoard, players: &[usize], first_depth: i32,
+           mut time_manager: Option<&mut TimeManager>, config: &EngineConfig) -> SearchResult {
+    let mut depth = first_depth - 1;
+    let mut result = SearchResult { best_move: Direction::None, score: 0, depth: 0, pv: Vec::new(), root_moves: Vec::new() };
 
-    // while start_time.elapsed().as_millis() < /*250*/10 && depth <= 50 {
-    while depth <= 9 {
+    while depth < config.max_depth {
         depth += 1;
 
-        singular = 0;
-        not_singular = 0;
+        let iteration_start = Instant::now();
+        let iteration_nodes = ctx.counters.nodes;
 
         let mut temp_best_move = Direction::None;
-        score = minimax(&mut board, &snake.id, &enemy_id, depth.clone(), 0, -9999999, 9999999,
-                        true,
-                        transposition_table,
-                        &mut nodes_searched, &mut temp_best_move, start_time,
-                        &mut path,
-                        &mut history, killers,
-                        &mut singular, &mut not_singular
-        );
+        ctx.root_moves.clear();
+        let temp_score = match config.search_mode {
+            SearchMode::Sequential => {
+                board.set_turn(players[0]);
+                minimax(ctx, board, players, 0, depth, 0, -9999999, 9999999, true, &mut temp_best_move)
+            }
+            SearchMode::Simultaneous => simultaneous(ctx, board, players, depth, 0, -9999999, 9999999,
+                                                     config.simultaneous_backup, &mut temp_best_move),
+        };
 
         // If the search cuts early, it will be bad, so we will prevent that from affecting the best move
-        // if start_time.elapsed().as_millis() <= /*290*/12 && temp_best_move != Direction::None {
+        if ctx.aborted {
+            break;
+        }
+
+        result.depth = depth;
+        result.score = temp_score;
         if temp_best_move != Direction::None {
+            result.best_move = temp_best_move;
+        }
+        result.pv.clone_from(&ctx.pv[0]);
+        result.root_moves.clone_from(&ctx.root_moves);
+        ctx.iterations.push(IterationStats {
+            depth,
+            nodes: ctx.counters.nodes - iteration_nodes,
+            time_ms: iteration_start.elapsed().as_secs_f64() * 1000.0,
+        });
+
+        if time_manager.is_some() {
+            debug!("Depth {}, Score {}, PV: {}", depth, result.score, format_pv(board, &result.pv));
+        }
 
-            best_move = temp_best_move.clone();
+        // A forced win won't get any better by looking deeper
+        if result.score > 50000 {
+            break;
+        }
+        if let Some(time_manager) = time_manager.as_deref_mut() {
+            if time_manager.should_stop(result.best_move) {
+                break;
+            }
         }
     }
 
-    // path.reverse();
-    println!("Nodes Searched: {}, Depth {}, Best Score: {}, Best Direction: {:?}", nodes_searched, depth, score, best_move);
-    println!("Ordered Ratio: {}", singular as f64 / (singular + not_singular + 1) as f64);
-
-    // println!("NPS: {}", nodes_searched as f64 / start_time.elapsed().as_secs_f64());
-    // println!("Best Path: {:?}", path);
-
-    // if score.abs() < 50000 {
-        record_round(score, board.clone(), &snake.id, &enemy_id, game_recorder);
-    // }
-
-    // let mut file = OpenOptions::new()
-    //     .write(true)
-    //     .append(true)
-    //     .create(true)
-    //     .open("/Users/ishaangoyal/RustroverProjects/starter-snake-rust/prunedata/prune_data_1.csv").unwrap();
-    //
-    // writeln!(file, "{}", nodes_searched).unwrap();
-
-    return best_move;
+    result
 }
 
-pub fn minimax(board: &mut GameBoard, snake_id: &str, enemy_id: &str, mut depth: i32, ply: i32, mut alpha: i32, mut beta: i32,
-               should_nmp: bool,
-               transposition_table: &mut Vec<TTEntry>,
-               nodes_searched: &mut i32, best_move: &mut Direction, start_time: Instant, past_moves: &mut Vec<Direction>,
-               history: &mut [[[[i32; 11]; 11]; 11]; 11], killers: &mut [(Coord, Coord); 1000],
-               singular: &mut i32, not_singular: &mut i32) -> i32 {
-    *nodes_searched += 1;
-
+#[allow(clippy::too_many_arguments)]
+// Scores are always from the point of view of the team of `players[to_move]`: us (index 0) on
+// our own, or every other snake together
+pub fn minimax(ctx: &mut SearchContext, board: &mut GameBoard, players: &[usize], to_move: usize, mut depth: i32, ply: i32,
+               mut alpha: i32, mut beta: i32,
+               should_nmp: bool, best_move: &mut Direction) -> i32 {
+    ctx.counters.nodes += 1;
+    ctx.counters.seldepth = ctx.counters.seldepth.max(ply);
+    ctx.clear_pv(ply);
+
+    if ctx.out_of_time() {
+        return 0;
+    }
 
-    let snake = board.get_snake(snake_id).clone();
-    let enemy = board.get_snake(enemy_id).clone();
+    let next = ctx.next_player(to_move);
+    let snake = players[to_move];
+    let enemy = players[next];
 
-    let possible_moves = board.generate_possible_moves(snake.clone());
+    let possible_moves = board.generate_possible_moves(snake);
 
     // Dead
-    if possible_moves.is_empty() || snake.borrow().health <= 0 {
-        return -100000 + ply;
+    if possible_moves.is_empty() || board.snakes[snake].health <= 0 {
+        if to_move == 0 || players.len() == 2 {
+            return -100000 + ply;
+        }
+
+        // An enemy that can't move is out, the rest of the game goes on without it
+        ctx.eliminated[to_move] = true;
+        let score = if ctx.eliminated.iter().skip(1).all(|e| *e) {
+            -100000 + ply
+        } else {
+            let score = search_child(ctx, board, players, to_move, ctx.next_player(to_move), depth, ply + 1,
+                                     alpha, beta, should_nmp, best_move);
+            ctx.update_pv(ply, None);
+            score
+        };
+        ctx.eliminated[to_move] = false;
+        return score;
     }
 
     // Potential game-ending branch
@@ -124,37 +439,36 @@ pub fn minimax(board: &mut GameBoard, snake_id: &str, enemy_id: &str, mut depth:
         depth += 1;
     }
 
-    // if depth <= 0 || start_time.elapsed().as_millis() >= /*300*/15 {
     if depth <= 0 {
-        return -eval(board, enemy.clone(), snake.clone());
+        if players.len() == 2 {
+            return -eval(board, enemy, snake);
+        }
+
+        let score = eval_multi(board, players, &ctx.eliminated);
+        return if to_move == 0 { score } else { -score };
     }
 
     let mut best_score = -999999;
 
-    let snake_head = snake.borrow().head.clone();
-    let enemy_head = enemy.borrow().head.clone();
-
-    let entry = &transposition_table[(board.zobrist_hash & 0x7FFFF) as usize];
-    let tt_hit = entry.zobrist == board.zobrist_hash
-        && entry.friendly_health == snake.borrow().health
-        && entry.enemy_health == enemy.borrow().health
-        && entry.snake_head_x == snake_head.x
-        && entry.snake_head_y == snake_head.y
-        && entry.enemy_head_x == enemy_head.x
-        && entry.enemy_head_y == enemy_head.y;
+    let snake_head = board.snakes[snake].head();
+    let enemy_head = board.snakes[enemy].head();
+
+    let probed = ctx.transposition_table.probe(board.zobrist_hash);
+    let tt_hit = probed.is_some();
+    ctx.counters.tt_probes += 1;
+    ctx.counters.tt_hits += tt_hit as u64;
+    let entry = probed.unwrap_or_default();
         // && entry.board_hash == board.board_hash;
 
     if ply > 0 && tt_hit && entry.depth >= depth {
         match entry.flag {
-            1 => {
-                alpha = alpha.max(entry.score);
-            }
-            2 => {
+            UPPER => {
                 beta = beta.min(entry.score);
             }
+            LOWER => {
+                alpha = alpha.max(entry.score);
+            }
             _ => {
-                // alpha = alpha.max(entry.score);
-                // beta = beta.min(entry.score);
                 return entry.score;
             }
         }
@@ -163,8 +477,15 @@ pub fn minimax(board: &mut GameBoard, snake_id: &str, enemy_id: &str, mut depth:
         }
     }
 
-    let mut tt_flag = 1;
+    let mut tt_flag = UPPER;
 
+    // Five random bits for every direction, see `SearchContext::rng`
+    let noise = ctx.rng.as_mut().map_or(0, |rng| rng.gen::<u64>());
+
+    let killers = &ctx.killers;
+    let history = &ctx.history;
+    let past_moves = &ctx.past_moves;
+    let not_empty = board.occupied() | board.food;
     let mut scored_moves: Vec<(Direction, i32)> = possible_moves.iter()
         .map(|dir| (*dir,
             // NO TONKAs, no AUs, no NEWTONMETERS, no INVERSEKILOJOULESPERMETERSSQUARED, no GOLDMAN, 6 ON AP PHYSICS ABCD
@@ -175,59 +496,60 @@ pub fn minimax(board: &mut GameBoard, snake_id: &str, enemy_id: &str, mut depth:
             } else if tt_hit && dir == &entry.worst_move {
                 -100_000_000
             } else {
-                let new_head = move_coord(&snake_head, dir);
+                let new_head = board.neighbour(snake_head, *dir);
 
                 if killers[ply as usize].0 == snake_head && killers[ply as usize].1 == new_head {
                     10_000_000
-                } else if history[snake_head.x as usize][snake_head.y as usize][new_head.x as usize][new_head.y as usize] > 0 {
-                    history[snake_head.x as usize][snake_head.y as usize][new_head.x as usize][new_head.y as usize] * 1000
+                } else if history[history_index(board, snake_head, *dir)] > 0 {
+                    history[history_index(board, snake_head, *dir)] * 1000
                 } else if past_moves.len() >= 4
                     && dir == &past_moves[past_moves.len() - 2]
                     && dir == &past_moves[past_moves.len() - 4] { // Tempo bonus
                     1_000
                 } else {
-                    let center_distance = (new_head.x - 5).abs() + (new_head.y - 5).abs();
-                    let center_distance_enemy = (enemy_head.x - 5).abs() + (enemy_head.y - 5).abs();
+                    let center_x = board.width / 2;
+                    let center_y = board.height / 2;
+                    let center_distance = (new_head.x - center_x).abs() + (new_head.y - center_y).abs();
+                    let center_distance_enemy = (enemy_head.x - center_x).abs() + (enemy_head.y - center_y).abs();
 
                     let mut num_adj_occupied = 0;
-                    if new_head.x == 10 || board.matrix[(new_head.x + 1) as usize][new_head.y as usize] != CellContent::Empty {
-                        num_adj_occupied += 100;
-                    }
-                    if new_head.y == 10 || board.matrix[new_head.x as usize][(new_head.y + 1) as usize] != CellContent::Empty {
-                        num_adj_occupied += 100;
-                    }
-                    if new_head.x == 0 || board.matrix[(new_head.x - 1) as usize][new_head.y as usize] != CellContent::Empty {
-                        num_adj_occupied += 100;
-                    }
-                    if new_head.y == 0 || board.matrix[new_head.x as usize][(new_head.y - 1) as usize] != CellContent::Empty {
-                        num_adj_occupied += 100;
+                    for adj_dir in [Direction::Right, Direction::Up, Direction::Left, Direction::Down] {
+                        let adj = board.neighbour(new_head, adj_dir);
+                        if !board.in_bounds(adj) || not_empty.get(board.layout.index(adj)) {
+                            num_adj_occupied += 100;
+                        }
                     }
 
-                    if board.matrix[new_head.x as usize][new_head.y as usize] == CellContent::Food {
-                        -1_000_000 + 50_000 - num_adj_occupied + (center_distance_enemy - center_distance)
+                    let jitter = (noise >> (*dir as u64 * 5) & 0x1F) as i32;
+
+                    if board.is_food(new_head) {
+                        -1_000_000 + 50_000 - num_adj_occupied + (center_distance_enemy - center_distance) + jitter
                     } else {
-                        -1_000_000 + -num_adj_occupied + (center_distance_enemy - center_distance)
+                        -1_000_000 + -num_adj_occupied + (center_distance_enemy - center_distance) + jitter
                     }
                 }
             }
     )).collect();
 
     // Null Move Pruning
-    if depth > 5 && should_nmp {
-        past_moves.push(Direction::None);
+    if depth > 5 && should_nmp && players.len() == 2 {
+        ctx.counters.nmp_tries += 1;
+        ctx.past_moves.push(Direction::None);
+        // The enemy's subtree is scored from its side, it mustn't share our entry in the table
+        let previous = board.set_turn(players[next]);
 
         // Give the enemy snake an extra move, if we are still doing better, then this is a great position
-        let nmp = -minimax(board, enemy_id, snake_id, 3, ply + 1, -beta, -alpha,
-                           false,
-                           transposition_table,
-                           nodes_searched, best_move, start_time,
-                           past_moves,
-                           history, killers,
-                           singular, not_singular);
-
-        past_moves.pop();
+        let nmp = -minimax(ctx, board, players, next, 3, ply + 1, -beta, -alpha,
+                           false, best_move);
+
+        board.set_turn(previous);
+        ctx.past_moves.pop();
+        if ctx.aborted {
+            return 0;
+        }
         // NMP fail-high
         if nmp >= beta {
+            ctx.counters.nmp_cutoffs += 1;
             return beta;
         }
     }
@@ -245,119 +567,370 @@ pub fn minimax(board: &mut GameBoard, snake_id: &str, enemy_id: &str, mut depth:
     let mut worst_score = 999999;
 
     // println!();
-    let mut i = 0;
-    for (dir, _score) in &scored_moves {
+    for (i, (dir, _score)) in scored_moves.iter().enumerate() {
         // let redux = if i > 1 { 1 } else { 0 };
         // let null_window = if i > 1 { true } else { false };
         let null_window = false;
 
-        board.move_snake(snake.clone(), dir.clone());
+        board.move_snake(snake, *dir);
 
-        past_moves.push(dir.clone());
+        ctx.past_moves.push(*dir);
 
         let new_score = if i <= 1 {
-            -minimax(board, enemy_id, snake_id, depth - 1, ply + 1,
-                     if null_window { -alpha - 1 } else { -beta }, -alpha,
-                     should_nmp,
-                     transposition_table,
-                     nodes_searched, best_move, start_time,
-                     past_moves,
-                     history, killers,
-                     singular, not_singular)
+            search_child(ctx, board, players, to_move, next, depth - 1, ply + 1,
+                         alpha, if null_window { alpha + 1 } else { beta },
+                         should_nmp, best_move)
         } else {
-            let temp_score = -minimax(board, enemy_id, snake_id, depth - 1 - 2, ply + 1,
-                                      -alpha - 1, -alpha,
-                                      should_nmp,
-                                      transposition_table,
-                                      nodes_searched, best_move, start_time,
-                                      past_moves,
-                                      history, killers,
-                                      singular, not_singular);
-
-            if temp_score > alpha {
-                -minimax(board, enemy_id, snake_id, depth - 1, ply + 1,
-                         -beta, -alpha,
-                         should_nmp,
-                         transposition_table,
-                         nodes_searched, best_move, start_time,
-                         past_moves,
-                         history, killers,
-                         singular, not_singular)
+            let temp_score = search_child(ctx, board, players, to_move, next, depth - 1 - 2, ply + 1,
+                                          alpha, alpha + 1,
+                                          should_nmp, best_move);
+
+            if temp_score > alpha && !ctx.aborted {
+                search_child(ctx, board, players, to_move, next, depth - 1, ply + 1,
+                             alpha, beta,
+                             should_nmp, best_move)
             } else {
                 temp_score
             }
         };
 
+        board.undo_move();
 
+        ctx.past_moves.pop();
 
-        board.undo_move(snake.clone());
-
-        past_moves.pop();
+        // The score of an unfinished subtree means nothing, and neither does anything built on it
+        if ctx.aborted {
+            return 0;
+        }
 
+        if ply == 0 {
+            ctx.root_moves.push(RootMove { direction: *dir, score: new_score, exact: new_score > alpha });
+        }
         if new_score > best_score {
             best_score = new_score;
-            second_local_best_move = local_best_move.clone();
-            local_best_move = dir.clone();
+            second_local_best_move = local_best_move;
+            local_best_move = *dir;
             if ply == 0 {
-                *best_move = dir.clone();
+                *best_move = *dir;
             }
         }
         if new_score > alpha {
             alpha = new_score;
-            tt_flag = 3;
+            tt_flag = EXACT;
+            ctx.update_pv(ply, Some(PvMove { snake, direction: *dir }));
             if alpha >= beta {
-                let new_head = move_coord(&snake_head, dir);
-                history[snake_head.x as usize][snake_head.y as usize][new_head.x as usize][new_head.y as usize] += depth * depth;
-                killers[ply as usize] = (snake_head, new_head);
-                tt_flag = 2;
+                let new_head = board.neighbour(snake_head, *dir);
+                ctx.history[history_index(board, snake_head, *dir)] += depth * depth;
+                ctx.killers[ply as usize] = (snake_head, new_head);
+                ctx.counters.cutoff(i);
+                tt_flag = LOWER;
                 break;
             }
         }
         if new_score < worst_score {
             worst_score = new_score;
-            worst_local_move = dir.clone();
+            worst_local_move = *dir;
         }
-
-        i += 1;
     }
 
-    // if local_best_move == scored_moves[0].0.clone() {
-    //     *num_ordered += 1;
-    // } else {
-    //     *num_unordered += 1
-    // }
-    if scored_moves.len() == 1 {
-        *singular += 1;
-    } else {
-        *not_singular += 1;
-    }
-
-    transposition_table[(board.zobrist_hash & 0x7FFFF) as usize] = TTEntry {
-        zobrist: board.zobrist_hash.clone(),
-        best_move: local_best_move.clone(),
-        second_best_move: second_local_best_move.clone(),
-        worst_move: worst_local_move.clone(),
-        friendly_health: snake.borrow().health.clone(),
-        enemy_health: enemy.borrow().health.clone(),
-        depth: depth.clone(),
-        score: best_score.clone(),
+    ctx.transposition_table.store(board.zobrist_hash, TTEntry {
+        best_move: local_best_move,
+        second_best_move: second_local_best_move,
+        worst_move: worst_local_move,
+        depth,
+        score: best_score,
         flag: tt_flag,
-        snake_head_x: snake_head.x.clone(),
-        snake_head_y: snake_head.y.clone(),
-        enemy_head_x: enemy_head.x.clone(),
-        enemy_head_y: enemy_head.y.clone(),
+    });
+
+    best_score
+}
+
+// Searches with every snake moving at once, each ply being one joint move that is resolved the
+// way the server resolves a turn. Scores are always from our (players[0]) point of view.
+#[allow(clippy::too_many_arguments)]
+pub fn simultaneous(ctx: &mut SearchContext, board: &mut GameBoard, players: &[usize], mut depth: i32, ply: i32,
+                    mut alpha: i32, mut beta: i32,
+                    backup: SimultaneousBackup, best_move: &mut Direction) -> i32 {
+    ctx.counters.nodes += 1;
+    ctx.counters.seldepth = ctx.counters.seldepth.max(ply);
+    ctx.clear_pv(ply);
+
+    if ctx.out_of_time() {
+        return 0;
+    }
+
+    let snake = players[0];
+    let enemy = players[ctx.next_player(0)];
+
+    let possible_moves = board.generate_legal_moves(snake);
+
+    // Dead
+    if possible_moves.is_empty() {
+        return -100000 + ply;
+    }
+
+    // Potential game-ending branch
+    if possible_moves.len() == 1 {
+        depth += 1;
+    }
+
+    if depth <= 0 {
+        if players.len() == 2 {
+            return -eval(board, enemy, snake);
+        }
+        return eval_multi(board, players, &ctx.eliminated);
+    }
+
+    let probed = ctx.transposition_table.probe(board.zobrist_hash);
+    let tt_hit = probed.is_some();
+    ctx.counters.tt_probes += 1;
+    ctx.counters.tt_hits += tt_hit as u64;
+    let entry = probed.unwrap_or_default();
+
+    if ply > 0 && tt_hit && entry.depth >= depth {
+        match entry.flag {
+            UPPER => beta = beta.min(entry.score),
+            LOWER => alpha = alpha.max(entry.score),
+            _ => return entry.score,
+        }
+        if alpha >= beta {
+            return entry.score;
+        }
+    }
+
+    // Enemies that can't go anywhere are left to `step`, which walks them into whatever kills them.
+    // With every enemy stuck that's still a turn to play, our move may be the end of us too.
+    let mut enemy_moves = Vec::new();
+    for (i, &enemy) in players.iter().enumerate().skip(1) {
+        if ctx.eliminated[i] {
+            continue;
+        }
+        let moves = board.generate_legal_moves(enemy);
+        if !moves.is_empty() {
+            enemy_moves.push((enemy, moves));
+        }
+    }
+
+    let mut ordered_moves = possible_moves.to_vec();
+    if tt_hit {
+        if let Some(index) = ordered_moves.iter().position(|dir| *dir == entry.best_move) {
+            ordered_moves[..=index].rotate_right(1);
+        }
+    }
+
+    // Every combination of enemy moves, the first enemy's move changing slowest
+    let mut combinations: Vec<Vec<Direction>> = vec![vec![]];
+    for (_, moves) in &enemy_moves {
+        combinations = combinations.iter()
+            .flat_map(|combination| moves.iter().map(move |dir| {
+                let mut combination = combination.clone();
+                combination.push(*dir);
+                combination
+            }))
+            .collect();
+    }
+
+    let mut movers = vec![snake];
+    movers.extend(enemy_moves.iter().map(|(enemy, _)| *enemy));
+
+    let original_alpha = alpha;
+    let mut local_best_move = Direction::None;
+    let mut best_score = -999999;
+    let mut scores: Vec<Vec<i32>> = Vec::new();
+    // For every row, the line after the enemies' best reply to it
+    let mut row_pvs: Vec<Vec<PvMove>> = Vec::new();
+
+    for (i, dir) in ordered_moves.iter().enumerate() {
+        let mut row_score = 999999;
+        let mut row = Vec::with_capacity(combinations.len());
+        let mut row_pv = Vec::new();
+
+        for combination in &combinations {
+            let mut moves = vec![(snake, *dir)];
+            moves.extend(movers[1..].iter().copied().zip(combination.iter().copied()));
+
+            board.step(&moves);
+            for (i, &player) in players.iter().enumerate() {
+                ctx.eliminated[i] = board.is_eliminated(player);
+            }
+            let enemies_dead = ctx.eliminated[1..].iter().all(|e| *e);
+            ctx.pv[ply as usize + 1].clear();
+
+            let score = if ctx.eliminated[0] {
+                if enemies_dead { 0 } else { -100000 + ply + 1 }
+            } else if enemies_dead {
+                100000 - ply - 1
+            } else {
+                let (child_alpha, child_beta) = match backup {
+                    SimultaneousBackup::Pessimistic => (alpha, row_score.min(beta)),
+                    SimultaneousBackup::Mixed => (-9999999, 9999999),
+                };
+                simultaneous(ctx, board, players, depth - 1, ply + 1, child_alpha, child_beta,
+                             backup, best_move)
+            };
+
+            board.undo_step();
+            for (i, &player) in players.iter().enumerate() {
+                ctx.eliminated[i] = board.is_eliminated(player);
+            }
+
+            if ctx.aborted {
+                return 0;
+            }
+
+            row.push(score);
+            if score < row_score {
+                row_score = score;
+                row_pv.clear();
+                row_pv.extend(moves.iter().map(|&(snake, direction)| PvMove { snake, direction }));
+                row_pv.extend_from_slice(&ctx.pv[ply as usize + 1]);
+            }
+
+            // The enemies already have a reply that keeps this move from beating what we have
+            if backup == SimultaneousBackup::Pessimistic && row_score <= alpha {
+                break;
+            }
+        }
+
+        scores.push(row);
+        row_pvs.push(row_pv);
+        if ply == 0 {
+            let exact = backup == SimultaneousBackup::Mixed || row_score > alpha;
+            ctx.root_moves.push(RootMove { direction: *dir, score: row_score, exact });
+        }
+
+        if backup == SimultaneousBackup::Pessimistic {
+            if row_score > best_score {
+                best_score = row_score;
+                local_best_move = *dir;
+            }
+            if best_score > alpha {
+                alpha = best_score;
+                if alpha >= beta {
+                    ctx.counters.cutoff(i);
+                    break;
+                }
+            }
+        }
+    }
+
+    let tt_flag = match backup {
+        SimultaneousBackup::Pessimistic => {
+            if best_score >= beta {
+                LOWER
+            } else if best_score <= original_alpha {
+                UPPER
+            } else {
+                EXACT
+            }
+        }
+        SimultaneousBackup::Mixed => {
+            let (value, strategy) = solve_matrix_game(&scores);
+            best_score = value;
+            local_best_move = ordered_moves[(0..strategy.len())
+                .max_by(|a, b| strategy[*a].total_cmp(&strategy[*b]))
+                .unwrap()];
+            EXACT
+        }
     };
 
-    return best_score;
+    if ply == 0 {
+        *best_move = local_best_move;
+    }
+    if let Some(index) = ordered_moves.iter().position(|dir| *dir == local_best_move) {
+        ctx.pv[ply as usize] = std::mem::take(&mut row_pvs[index]);
+    }
+
+    ctx.transposition_table.store(board.zobrist_hash, TTEntry {
+        best_move: local_best_move,
+        second_best_move: Direction::None,
+        worst_move: Direction::None,
+        depth,
+        score: best_score,
+        flag: tt_flag,
+    });
+
+    best_score
+}
+
+// Value of the zero-sum game with payoff matrix `scores` (rows are our moves, columns the enemy
+// replies) and our mixed strategy, approximated with regret matching
+fn solve_matrix_game(scores: &[Vec<i32>]) -> (i32, Vec<f64>) {
+    const ITERATIONS: usize = 256;
+
+    let rows = scores.len();
+    let columns = scores[0].len();
+
+    let mut row_regrets = vec![0.0; rows];
+    let mut column_regrets = vec![0.0; columns];
+    let mut row_strategy_sum = vec![0.0; rows];
+    let mut column_strategy_sum = vec![0.0; columns];
+
+    for _ in 0..ITERATIONS {
+        let row_strategy = regret_matching(&row_regrets);
+        let column_strategy = regret_matching(&column_regrets);
+
+        let row_values: Vec<f64> = (0..rows)
+            .map(|r| (0..columns).map(|c| scores[r][c] as f64 * column_strategy[c]).sum())
+            .collect();
+        let column_values: Vec<f64> = (0..columns)
+            .map(|c| (0..rows).map(|r| scores[r][c] as f64 * row_strategy[r]).sum())
+            .collect();
+        let value: f64 = (0..rows).map(|r| row_values[r] * row_strategy[r]).sum();
+
+        for r in 0..rows {
+            row_regrets[r] = (row_regrets[r] + row_values[r] - value).max(0.0);
+            row_strategy_sum[r] += row_strategy[r];
+        }
+        // The enemies are minimising
+        for c in 0..columns {
+            column_regrets[c] = (column_regrets[c] + value - column_values[c]).max(0.0);
+            column_strategy_sum[c] += column_strategy[c];
+        }
+    }
+
+    let row_strategy: Vec<f64> = row_strategy_sum.iter().map(|s| s / ITERATIONS as f64).collect();
+    let column_strategy: Vec<f64> = column_strategy_sum.iter().map(|s| s / ITERATIONS as f64).collect();
+    let value: f64 = (0..rows)
+        .map(|r| (0..columns).map(|c| scores[r][c] as f64 * row_strategy[r] * column_strategy[c]).sum::<f64>())
+        .sum();
+
+    (value.round() as i32, row_strategy)
 }
 
-fn sort_2(arr: &mut Vec<(Direction, i32)>) {
+fn regret_matching(regrets: &[f64]) -> Vec<f64> {
+    let total: f64 = regrets.iter().sum();
+    if total > 0.0 {
+        regrets.iter().map(|r| r / total).collect()
+    } else {
+        vec![1.0 / regrets.len() as f64; regrets.len()]
+    }
+}
+
+// Searches the position with `next` to move and hands the score back from the point of view of
+// `to_move`. Enemies moving after each other are on the same side, so only a switch between us
+// and them flips the sign and the window.
+#[allow(clippy::too_many_arguments)]
+fn search_child(ctx: &mut SearchContext, board: &mut GameBoard, players: &[usize], to_move: usize, next: usize,
+                depth: i32, ply: i32, alpha: i32, beta: i32,
+                should_nmp: bool, best_move: &mut Direction) -> i32 {
+    let previous = board.set_turn(players[next]);
+    let score = if (to_move == 0) != (next == 0) {
+        -minimax(ctx, board, players, next, depth, ply, -beta, -alpha, should_nmp, best_move)
+    } else {
+        minimax(ctx, board, players, next, depth, ply, alpha, beta, should_nmp, best_move)
+    };
+    board.set_turn(previous);
+    score
+}
+
+fn sort_2(arr: &mut [(Direction, i32)]) {
     if arr[1].1 > arr[0].1 {
         arr.swap(0, 1);
     }
 }
 
-fn sort_3(arr: &mut Vec<(Direction, i32)>) {
+fn sort_3(arr: &mut [(Direction, i32)]) {
     if arr[1].1 > arr[0].1 {
         arr.swap(0, 1);
     }
@@ -369,36 +942,19 @@ fn sort_3(arr: &mut Vec<(Direction, i32)>) {
     }
 }
 
-fn move_coord(coord: &Coord, dir: &Direction) -> Coord {
-    match dir {
-        Direction::Up => Coord {
-            x: coord.x,
-            y: coord.y + 1,
-        },
-        Direction::Down => Coord {
-            x: coord.x,
-            y: coord.y - 1,
-        },
-        Direction::Left => Coord {
-            x: coord.x - 1,
-            y: coord.y,
-        },
-        Direction::Right => Coord {
-            x: coord.x + 1,
-            y: coord.y,
-        },
-        Direction::None => Coord {
-            x: coord.x,
-            y: coord.y,
-        },
-    }
+// Where a move from `from` in `direction` lives in `SearchContext::history`, four moves a cell
+// so the table stays small enough to start afresh on every move even on the biggest boards
+fn history_index(board: &GameBoard, from: Coord, direction: Direction) -> usize {
+    board.layout.index(from) * 4 + direction as usize
 }
 
-fn before_endgame(board: &GameBoard, snake: Rc<RefCell<Battlesnake>>, enemy: Rc<RefCell<Battlesnake>>) -> bool {
-    return (snake.borrow().length + enemy.borrow().length) > (board.width * board.height) / 4;
+#[allow(dead_code)]
+fn before_endgame(board: &GameBoard, snake: usize, enemy: usize) -> bool {
+    (board.snakes[snake].length + board.snakes[enemy].length) > (board.width * board.height) / 4
 }
 
-pub fn record_round(target_score: i32, board: GameBoard, snake_id: &str, enemy_id: &str, game_recorder: &mut GameRecorder) {
+#[allow(clippy::needless_range_loop)]
+pub fn record_round(target_score: i32, board: &GameBoard, snake: usize, enemy: usize, game_recorder: &mut GameRecorder) {
     // let mut file = OpenOptions::new()
     //     .write(true)
     //     .append(true)
@@ -413,36 +969,26 @@ pub fn record_round(target_score: i32, board: GameBoard, snake_id: &str, enemy_i
         return;
     }
 
-    let snake = board.get_snake(snake_id);
-    let enemy = board.get_snake(enemy_id);
+    let snake = &board.snakes[snake];
+    let enemy = &board.snakes[enemy];
 
-    let snake_x = snake.borrow().head.x;
-    let snake_y = snake.borrow().head.y;
-    let enemy_x = enemy.borrow().head.x;
-    let enemy_y = enemy.borrow().head.y;
+    let snake_x = snake.head().x;
+    let snake_y = snake.head().y;
+    let enemy_x = enemy.head().x;
+    let enemy_y = enemy.head().y;
 
-    let snake_length = snake.borrow().length;
-    let enemy_length = enemy.borrow().length;
+    let snake_length = snake.length;
+    let enemy_length = enemy.length;
 
-    let mut bfs_term: i32 = 0;
-    let mut health_term: i32 = snake.borrow().health.clone();
-    let mut length_diff_term: i32 = snake.borrow().length.clone() - enemy.borrow().length.clone();
+    let health_term: i32 = snake.health;
 
 
-    let mut passability_matrix: [[bool; 11]; 11] = [[false; 11]; 11];
-
-    for x in 0..board.width as usize {
-        for y in 0..board.height as usize {
-            if board.matrix[x][y] == CellContent::Empty || board.matrix[x][y] == CellContent::Food {
-                passability_matrix[x][y] = true;
-            }
-        }
-    }
+    let passability_matrix: Grid<i32> = passability(board);
 
     // let snake_bfs = crate::eval::bfs(&passability_matrix, snake_x as usize, snake_y as usize);
     // let enemy_bfs = crate::eval::bfs(&passability_matrix, enemy_x as usize, enemy_y as usize);
 
-    let (snake_bfs, enemy_bfs) = bfs(&passability_matrix,
+    let (snake_bfs, enemy_bfs) = bfs(&passability_matrix, wrap_dimensions(board),
                                      snake_x as usize, snake_y as usize,
                                      enemy_x as usize, enemy_y as usize);
     let mut bfs_snake = 0;
@@ -450,10 +996,10 @@ pub fn record_round(target_score: i32, board: GameBoard, snake_id: &str, enemy_i
 
     for x in 0..board.width as usize {
         for y in 0..board.height as usize {
-            if passability_matrix[x][y] {
-                if snake_bfs[x][y] < enemy_bfs[x][y] {
+            if passability_matrix[(x, y)] > 0 {
+                if snake_bfs[(x, y)] < enemy_bfs[(x, y)] {
                     bfs_snake += 1;
-                } else if enemy_bfs[x][y] < snake_bfs[x][y] {
+                } else if enemy_bfs[(x, y)] < snake_bfs[(x, y)] {
                     bfs_enemy += 1;
                 } else if snake_length > enemy_length {
                     bfs_snake += 1;
@@ -473,4 +1019,200 @@ pub fn record_round(target_score: i32, board: GameBoard, snake_id: &str, enemy_i
         health: health_term,
     });
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::board::Ruleset;
+    use super::*;
+
+    fn snake(id: &str, body: &[(i32, i32)]) -> Battlesnake {
+        let body: Vec<Coord> = body.iter().map(|&(x, y)| Coord { x, y }).collect();
+        Battlesnake {
+            id: id.to_string(),
+            name: id.to_string(),
+            health: 90,
+            head: body[0],
+            length: body.len() as i32,
+            body,
+            latency: String::new(),
+            shout: None,
+        }
+    }
+
+    fn board(width: i32, height: i32, snakes: Vec<Battlesnake>) -> GameBoard {
+        GameBoard::new(width, height, vec![], snakes, vec![], Ruleset::default())
+    }
+
+    // A sequential search of `depth` plies from the root with snake 0 to move, the rest from the
+    // nearest on
+    fn search_sequential(board: &mut GameBoard, players: &[usize], depth: i32) -> (i32, Direction, SearchCounters) {
+        let table = TranspositionTable::new(1);
+        let mut killers = [(Coord { x: -1, y: -1 }, Coord { x: -1, y: -1 }); 1000];
+        let stop = AtomicBool::new(false);
+        let deadline = Instant::now() + Duration::from_secs(600);
+        let mut ctx = SearchContext::new(board, players, &table, &mut killers, deadline, &stop);
+
+        let mut best_move = Direction::None;
+        board.set_turn(players[0]);
+        let score = minimax(&mut ctx, board, players, 0, depth, 0, -9999999, 9999999, true, &mut best_move);
+        (score, best_move, ctx.counters)
+    }
+
+    fn search_simultaneous(board: &mut GameBoard, players: &[usize], depth: i32,
+                           backup: SimultaneousBackup) -> (i32, Direction) {
+        let table = TranspositionTable::new(1);
+        let mut killers = [(Coord { x: -1, y: -1 }, Coord { x: -1, y: -1 }); 1000];
+        let stop = AtomicBool::new(false);
+        let deadline = Instant::now() + Duration::from_secs(600);
+        let mut ctx = SearchContext::new(board, players, &table, &mut killers, deadline, &stop);
+
+        let mut best_move = Direction::None;
+        let score = simultaneous(&mut ctx, board, players, depth, 0, -9999999, 9999999, backup, &mut best_move);
+        (score, best_move)
+    }
+
+    #[test]
+    fn paranoid_search_sees_a_third_snake_close_the_only_exit() {
+        // We have (0, 2) and (0, 3) to get out by, walled in by `c`. `b` is nearer but harmless.
+        let walled_in = |c: &[(i32, i32)]| board(11, 11, vec![
+            snake("a", &[(0, 1), (0, 0), (0, 0)]),
+            snake("b", &[(2, 1), (3, 1), (4, 1)]),
+            snake("c", c),
+        ]);
+
+        // `c` moves into (0, 3) as soon as we are at (0, 2)
+        let mut board = walled_in(&[(1, 3), (1, 2), (1, 1), (1, 0), (2, 0), (3, 0), (4, 0)]);
+        let (score, _, _) = search_sequential(&mut board, &[0, 1, 2], 6);
+        assert!(score < -50000, "{}", score);
+
+        // Turned around, `c` can't get there and its tail lets us out on the other side
+        let mut board = walled_in(&[(4, 0), (3, 0), (2, 0), (1, 0), (1, 1), (1, 2), (1, 3)]);
+        let (score, best_move, _) = search_sequential(&mut board, &[0, 1, 2], 6);
+        assert!(score > -50000, "{}", score);
+        assert_eq!(best_move, Direction::Up);
+    }
+
+    #[test]
+    fn the_pv_starts_with_the_best_move_and_takes_turns() {
+        // Walled in by `c` with the way out up, as above
+        let mut board = board(11, 11, vec![
+            snake("a", &[(0, 1), (0, 0), (0, 0)]),
+            snake("b", &[(2, 1), (3, 1), (4, 1)]),
+            snake("c", &[(4, 0), (3, 0), (2, 0), (1, 0), (1, 1), (1, 2), (1, 3)]),
+        ]);
+        let players = [0, 1, 2];
+        let config = EngineConfig { max_depth: 5, ..EngineConfig::default() };
+        let table = TranspositionTable::new(1);
+        let mut killers = [(Coord { x: -1, y: -1 }, Coord { x: -1, y: -1 }); 1000];
+        let stop = AtomicBool::new(false);
+        let deadline = Instant::now() + Duration::from_secs(600);
+        let mut ctx = SearchContext::new(&board, &players, &table, &mut killers, deadline, &stop);
+
+        let result = iterate(&mut ctx, &mut board, &players, 1, None, &config);
+
+        assert_eq!(result.depth, 5);
+        assert_eq!(result.best_move, Direction::Up);
+        assert!(result.pv.len() >= players.len(), "{}", format_pv(&board, &result.pv));
+        assert_eq!(result.pv[0].direction, result.best_move);
+        // Nobody dies on the way, so every snake has its turn in the search's order
+        for (ply, pv_move) in result.pv.iter().enumerate() {
+            assert_eq!(pv_move.snake, players[ply % players.len()], "{}", format_pv(&board, &result.pv));
+        }
+    }
+
+    #[test]
+    fn searches_split_the_helpers_and_give_them_back() {
+        let pool = search_pool(5).unwrap();
+
+        let first = pool.lend(2);
+        assert_eq!(first.count, 2);
+        // Two searches at once get half of the helpers each at most
+        let second = pool.lend(4);
+        assert_eq!(second.count, 2);
+        let third = pool.lend(4);
+        assert_eq!(third.count, 0);
+
+        drop(first);
+        drop(second);
+        drop(third);
+        assert_eq!(pool.lend(4).count, 4);
+    }
+
+    #[test]
+    fn matching_pennies_is_an_even_coin_flip() {
+        let (value, strategy) = solve_matrix_game(&[vec![100, -100], vec![-100, 100]]);
+
+        assert_eq!(value, 0);
+        assert!(strategy.iter().all(|p| (p - 0.5).abs() < 0.01), "{:?}", strategy);
+    }
+
+    #[test]
+    fn dominated_moves_are_not_played() {
+        // Our first move is better whatever the enemy does, and the enemy's best reply to it is its first
+        let (value, strategy) = solve_matrix_game(&[vec![10, 20], vec![0, 5], vec![-50, 8]]);
+
+        assert!((value - 10).abs() <= 1, "{}", value);
+        assert!(strategy[0] > 0.95, "{:?}", strategy);
+    }
+
+    #[test]
+    fn joint_moves_see_head_to_heads_as_trades() {
+        // Both snakes have nowhere to go but (0, 1), and they are the same length
+        let mut board = board(11, 11, vec![
+            snake("a", &[(0, 0), (1, 0), (1, 1), (1, 2), (2, 2)]),
+            snake("b", &[(0, 2), (0, 3), (0, 4), (0, 5), (0, 6)]),
+        ]);
+
+        // Taking turns, we won't step next to a head that's as long as ours and have nothing else
+        let (sequential, _, _) = search_sequential(&mut board, &[0, 1], 2);
+        assert_eq!(sequential, -100000);
+
+        // Moving together, we both go in and both lose, a draw
+        for backup in [SimultaneousBackup::Pessimistic, SimultaneousBackup::Mixed] {
+            let (simultaneous, best_move) = search_simultaneous(&mut board, &[0, 1], 2, backup);
+            assert_eq!((simultaneous, best_move), (0, Direction::Up));
+        }
+    }
+
+    #[test]
+    fn stuck_enemies_are_no_win_when_our_move_ends_us_too() {
+        let cornered = |health: i32| {
+            let mut us = snake("a", &[(0, 0), (1, 0), (2, 0)]);
+            us.health = health;
+            // Cornered by its own body, its only way on is off the board
+            let stuck = snake("b", &[(10, 10), (9, 10), (9, 9), (10, 9), (10, 8)]);
+            board(11, 11, vec![us, stuck])
+        };
+        let mut board = cornered(1);
+        assert!(board.generate_legal_moves(1).is_empty());
+
+        // Up is our only move and we starve making it, as the enemy dies making its own
+        for backup in [SimultaneousBackup::Pessimistic, SimultaneousBackup::Mixed] {
+            let (score, best_move) = search_simultaneous(&mut board, &[0, 1], 3, backup);
+            assert_eq!((score, best_move), (0, Direction::Up));
+        }
+
+        // Fed, we outlive it
+        let (score, best_move) = search_simultaneous(&mut cornered(90), &[0, 1], 3, SimultaneousBackup::Pessimistic);
+        assert_eq!((score, best_move), (99999, Direction::Up));
+    }
+
+    #[test]
+    fn null_moves_leave_the_board_as_they_found_it() {
+        let mut board = board(11, 11, vec![
+            snake("a", &[(2, 2), (2, 1), (2, 0)]),
+            snake("b", &[(8, 8), (8, 9), (8, 10)]),
+        ]);
+        let before = board.zobrist_hash;
+
+        // Deep enough for null moves, every change they make is checked with `verify-hash`
+        let (_, best_move, counters) = search_sequential(&mut board, &[0, 1], 7);
+
+        assert!(counters.nmp_tries > 0);
+        assert_ne!(best_move, Direction::None);
+        assert_eq!(board.zobrist_hash, before);
+        assert_eq!(board.zobrist_hash, board.compute_hash());
+    }
+}
@@ -0,0 +1,543 @@
+use log::{debug, error, info, warn};
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Status};
+use rocket::serde::json::{json, Json};
+use rocket::{Build, Rocket, State};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::{logic, GameState, JsonGameState};
+use crate::config::EngineConfig;
+use crate::game_recorder::{GameRecorder, TrainingDataWriter};
+use crate::metrics::{Eviction, Incident, Metrics};
+use crate::replay::{Decision, ReplayEvent, ReplayWriter};
+use crate::search::{search_pool, SearchPool};
+
+// A tracked game. When it was last used and how much memory it holds sit next to its lock, so
+// the reaper and `/metrics` never wait on a search to read them.
+struct GameSlot {
+    state: Mutex<GameState>,
+    // Milliseconds since `SharedState::started`
+    last_used: AtomicU64,
+    memory_bytes: AtomicU64,
+}
+
+impl GameSlot {
+    fn new(game: GameState, now_ms: u64) -> Self {
+        Self {
+            memory_bytes: AtomicU64::new(game.memory_bytes() as u64),
+            state: Mutex::new(game),
+            last_used: AtomicU64::new(now_ms),
+        }
+    }
+}
+
+type Games = BTreeMap<String, Arc<GameSlot>>;
+
+// Managed behind an `Arc` so searches can take it along to a blocking thread
+struct SharedState {
+    // Every game has its own lock, so a search only ever holds up later requests of its own game.
+    // The map's lock is only held long enough to find, add or remove a game.
+    games: RwLock<Games>,
+    config: EngineConfig,
+    // Helper threads for every search, `/move`s and `/analyze`s alike
+    search_pool: SearchPool,
+    metrics: Metrics,
+    started: Instant,
+    training_data: TrainingDataWriter,
+    replays: ReplayWriter,
+}
+
+impl SharedState {
+    fn now_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+#[get("/")]
+fn handle_index() -> Json<Value> {
+    Json(logic::info())
+}
+
+#[post("/start", format = "json", data = "<start_req>")]
+async fn handle_start(start_req: Json<JsonGameState>, state: &State<Arc<SharedState>>) -> Status {
+    // Allocating the table takes a while, not on one of the async workers
+    let start_req = start_req.into_inner();
+    let game_id = start_req.game.id.clone();
+    let response = {
+        let state = Arc::clone(state);
+        rocket::tokio::task::spawn_blocking(move || start_game(&start_req, &state)).await
+    };
+
+    response.unwrap_or_else(|join_error| {
+        error!("Starting game {} failed: {}", game_id, join_error);
+        Status::InternalServerError
+    })
+}
+
+fn start_game(start_req: &JsonGameState, state: &SharedState) -> Status {
+    let start_time = std::time::Instant::now();
+
+    let mut game = GameState::new(start_req, &state.config);
+    logic::start(&mut game, &state.config);
+    let slot = Arc::new(GameSlot::new(game, state.now_ms()));
+
+    let lock_start = std::time::Instant::now();
+    let mut games = write_games(state, &start_req.game.id);
+    state.metrics.lock_wait.observe_duration(lock_start.elapsed());
+
+    make_room(state, &mut games);
+    games.insert(start_req.game.id.clone(), slot);
+    drop(games);
+
+    state.replays.record(&start_req.game.id, ReplayEvent::Start { request: start_req.clone() });
+    info!("Started game {} in {}ms", start_req.game.id, start_time.elapsed().as_millis());
+
+    Status::Ok
+}
+
+#[post("/move", format = "json", data = "<move_req>")]
+async fn handle_move(move_req: Json<JsonGameState>, state: &State<Arc<SharedState>>) -> Json<Value> {
+    // Everything from here on is paid for out of the move timeout
+    let start_time = std::time::Instant::now();
+
+    // The search blocks for most of the timeout, it can't have one of the async workers
+    let move_req = Arc::new(move_req.into_inner());
+    let response = {
+        let (move_req, state) = (Arc::clone(&move_req), Arc::clone(state));
+        rocket::tokio::task::spawn_blocking(move || make_move(&move_req, &state, start_time)).await
+    };
+
+    match response {
+        Ok(response) => Json(response),
+        // `make_move` catches panics in the search, this is one outside of it
+        Err(_join_error) => {
+            report(state, Incident::SearchPanic, &move_req.game.id);
+            Json(logic::fallback_move(&move_req))
+        }
+    }
+}
+
+fn make_move(move_req: &JsonGameState, state: &SharedState, start_time: std::time::Instant) -> Value {
+    if let Err(e) = logic::searchable(&move_req.board) {
+        warn!("Game {} can't be searched, {}", move_req.game.id, e);
+        return unsearched_move(move_req, state, start_time);
+    }
+
+    let lock_start = std::time::Instant::now();
+    let slot = game_handle(move_req, state);
+    let mut game = lock_game(state, &slot.state, move_req);
+    state.metrics.lock_wait.observe_duration(lock_start.elapsed());
+
+    game.game = move_req.game.clone();
+    game.board = move_req.board.clone();
+    game.turn = move_req.turn;
+    game.you = move_req.you.clone();
+
+    // Caught here, while the game's lock is still held, a panic doesn't poison it
+    let response = panic::catch_unwind(AssertUnwindSafe(|| logic::get_move(
+        &mut game,
+        &state.config,
+        &state.search_pool,
+        &state.metrics,
+        start_time,
+    )));
+
+    let (response, decision) = match response {
+        Ok((response, result)) => {
+            let decision = Decision::searched(response["move"].as_str().unwrap_or("up"), &result, &move_req.board);
+            (response, decision)
+        }
+        Err(_panic) => {
+            report(state, Incident::SearchPanic, &move_req.game.id);
+            // Whatever the search left behind can't be trusted, the game starts over from here
+            *game = GameState::new(move_req, &state.config);
+            logic::start(&mut game, &state.config);
+            let response = logic::fallback_move(move_req);
+            let decision = Decision::fallback(response["move"].as_str().unwrap_or("up"));
+            (response, decision)
+        }
+    };
+    state.replays.record(&move_req.game.id, ReplayEvent::Move { request: move_req.clone(), decision });
+
+    slot.memory_bytes.store(game.memory_bytes() as u64, Ordering::Relaxed);
+    state.metrics.record_move(start_time.elapsed(), move_req.game.timeout);
+    response
+}
+
+// The fallback move, recorded and timed like a searched one
+fn unsearched_move(move_req: &JsonGameState, state: &SharedState, start_time: std::time::Instant) -> Value {
+    let response = logic::fallback_move(move_req);
+    let decision = Decision::fallback(response["move"].as_str().unwrap_or("up"));
+    state.replays.record(&move_req.game.id, ReplayEvent::Move { request: move_req.clone(), decision });
+    state.metrics.record_move(start_time.elapsed(), move_req.game.timeout);
+    response
+}
+
+// The game a request belongs to, started on the spot if `/start` never made it
+fn game_handle(req: &JsonGameState, state: &SharedState) -> Arc<GameSlot> {
+    if let Some(slot) = read_games(state, &req.game.id).get(&req.game.id) {
+        slot.last_used.store(state.now_ms(), Ordering::Relaxed);
+        return Arc::clone(slot);
+    }
+
+    warn!("Game {} not found, the /start endpoint might be backed up, creating it now", req.game.id);
+
+    let mut game = GameState::new(req, &state.config);
+    logic::start(&mut game, &state.config);
+    let slot = Arc::new(GameSlot::new(game, state.now_ms()));
+
+    // Another request of the same game may have got here first
+    let mut games = write_games(state, &req.game.id);
+    if let Some(slot) = games.get(&req.game.id) {
+        return Arc::clone(slot);
+    }
+    make_room(state, &mut games);
+    games.insert(req.game.id.clone(), Arc::clone(&slot));
+    slot
+}
+
+// Drops the least recently used games until there's room for one more. A search still running
+// in one of them finishes on its own copy.
+fn make_room(state: &SharedState, games: &mut Games) {
+    for id in least_recently_used(games, state.config.max_games) {
+        evicted(state, Eviction::Capacity, &id);
+    }
+}
+
+// What the reaper thread does every `reap_interval`
+fn evict_idle_games(state: &SharedState) {
+    let idle_ms = state.config.game_idle_timeout_secs * 1000;
+
+    let mut games = write_games(state, "-");
+    for id in idle_games(&mut games, state.now_ms(), idle_ms) {
+        evicted(state, Eviction::Idle, &id);
+    }
+
+    if !games.is_empty() {
+        debug!("Tracking {} games in about {} MB", games.len(), games_memory_bytes(&games) >> 20);
+    }
+}
+
+// Removes games, least recently used first, until fewer than `max_games` are left and returns
+// their ids
+fn least_recently_used(games: &mut Games, max_games: usize) -> Vec<String> {
+    let mut removed = Vec::new();
+    while !games.is_empty() && games.len() >= max_games {
+        let oldest = games.iter()
+            .min_by_key(|(_, slot)| slot.last_used.load(Ordering::Relaxed))
+            .map(|(id, _)| id.clone())
+            .unwrap();
+        games.remove(&oldest);
+        removed.push(oldest);
+    }
+    removed
+}
+
+// Removes the games last used more than `idle_ms` before `now_ms` and returns their ids
+fn idle_games(games: &mut Games, now_ms: u64, idle_ms: u64) -> Vec<String> {
+    let idle: Vec<String> = games.iter()
+        .filter(|(_, slot)| now_ms.saturating_sub(slot.last_used.load(Ordering::Relaxed)) > idle_ms)
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in &idle {
+        games.remove(id);
+    }
+    idle
+}
+
+fn reap_interval(config: &EngineConfig) -> Duration {
+    Duration::from_secs((config.game_idle_timeout_secs / 4).clamp(1, 30))
+}
+
+fn games_memory_bytes(games: &Games) -> u64 {
+    games.values().map(|slot| slot.memory_bytes.load(Ordering::Relaxed)).sum()
+}
+
+fn evicted(state: &SharedState, eviction: Eviction, game_id: &str) {
+    info!("Evicted game {} ({})", game_id, eviction.label());
+    state.metrics.record_eviction(eviction);
+}
+
+// A poisoned lock only means some request panicked while holding it. Nothing leaves the map half
+// changed, so it's used as it is.
+fn read_games<'a>(state: &'a SharedState, game_id: &str) -> RwLockReadGuard<'a, Games> {
+    state.games.read().unwrap_or_else(|poison_error| {
+        report(state, Incident::PoisonedLock, game_id);
+        state.games.clear_poison();
+        poison_error.into_inner()
+    })
+}
+
+fn write_games<'a>(state: &'a SharedState, game_id: &str) -> RwLockWriteGuard<'a, Games> {
+    state.games.write().unwrap_or_else(|poison_error| {
+        report(state, Incident::PoisonedLock, game_id);
+        state.games.clear_poison();
+        poison_error.into_inner()
+    })
+}
+
+// A game is another story, whatever a panic cut short in it can't be trusted. It's started over
+// from `req`.
+fn lock_game<'a>(state: &SharedState, game: &'a Mutex<GameState>, req: &JsonGameState) -> MutexGuard<'a, GameState> {
+    game.lock().unwrap_or_else(|poison_error| {
+        report(state, Incident::PoisonedLock, &req.game.id);
+        game.clear_poison();
+        let mut guard = poison_error.into_inner();
+        *guard = GameState::new(req, &state.config);
+        logic::start(&mut guard, &state.config);
+        guard
+    })
+}
+
+fn report(state: &SharedState, incident: Incident, game_id: &str) {
+    error!("Recovered from {} in game {}", incident.label(), game_id);
+    state.metrics.record_incident(incident);
+}
+
+// Not part of the game API, for Prometheus to scrape
+#[get("/metrics")]
+fn handle_metrics(state: &State<Arc<SharedState>>) -> (ContentType, String) {
+    let (active_games, memory_bytes) = match state.games.read() {
+        Ok(games) => (games.len(), games_memory_bytes(&games)),
+        Err(poison_error) => (poison_error.get_ref().len(), games_memory_bytes(poison_error.get_ref())),
+    };
+
+    (ContentType::Plain, state.metrics.render(active_games, memory_bytes))
+}
+
+// Not part of the game API, tells what the snake would do in a position and why
+#[post("/analyze", format = "json", data = "<analyze_req>")]
+async fn handle_analyze(analyze_req: Json<JsonGameState>, state: &State<Arc<SharedState>>) -> Json<Value> {
+    let start_time = std::time::Instant::now();
+
+    let state = Arc::clone(state);
+    let analyze_req = analyze_req.into_inner();
+    let response = rocket::tokio::task::spawn_blocking(move || {
+        logic::analyze(&analyze_req, &state.config, &state.search_pool, start_time)
+    }).await;
+
+    match response {
+        Ok(response) => Json(response),
+        Err(join_error) => {
+            error!("Analysis failed: {}", join_error);
+            Json(json!({ "error": "analysis failed" }))
+        }
+    }
+}
+
+#[post("/end", format = "json", data = "<end_req>")]
+async fn handle_end(end_req: Json<JsonGameState>, state: &State<Arc<SharedState>>) -> Status {
+    // Waiting for the game's lock blocks, not on one of the async workers
+    let end_req = end_req.into_inner();
+    let game_id = end_req.game.id.clone();
+    let response = {
+        let state = Arc::clone(state);
+        rocket::tokio::task::spawn_blocking(move || end_game(&end_req, &state)).await
+    };
+
+    response.unwrap_or_else(|join_error| {
+        error!("Ending game {} failed: {}", game_id, join_error);
+        Status::InternalServerError
+    })
+}
+
+fn end_game(end_req: &JsonGameState, state: &SharedState) -> Status {
+    let lock_start = std::time::Instant::now();
+    state.replays.record(&end_req.game.id, ReplayEvent::End { request: end_req.clone() });
+
+    // Nothing to wrap up for a game we never saw or already forgot about
+    let slot = match write_games(state, &end_req.game.id).remove(&end_req.game.id) {
+        Some(slot) => slot,
+        None => {
+            report(state, Incident::UnknownGame, &end_req.game.id);
+            return Status::Ok;
+        }
+    };
+
+    // Waits for a search of this game that might still be running
+    let mut game = lock_game(state, &slot.state, end_req);
+    state.metrics.lock_wait.observe_duration(lock_start.elapsed());
+
+    // Written out by another thread, nothing here waits on the disk
+    let game_recorder = std::mem::replace(&mut game.game_recorder, GameRecorder { rounds: vec![], ending: 0.5 });
+    state.training_data.submit(&end_req.game.id, game_recorder);
+
+    info!("Ended game {}", end_req.game.id);
+    logic::end(&end_req.game, &end_req.turn, &end_req.board, &end_req.you, &game.stats, &state.metrics);
+
+
+    Status::Ok
+}
+
+pub fn rocket() -> Rocket<Build> {
+    // Lots of web hosting services expect you to bind to the port specified by the `PORT`
+    // environment variable. However, Rocket looks at the `ROCKET_PORT` environment variable.
+    // If we find a value for `PORT`, we set `ROCKET_PORT` to that value.
+    if let Ok(port) = env::var("PORT") {
+        env::set_var("ROCKET_PORT", &port);
+    }
+
+    // TODO this is debug
+    // if env::var("RUST_LOG").is_err() {
+    //     env::set_var("RUST_LOG", "info");
+    // }
+
+    env_logger::init();
+
+    info!("Starting Battlesnake Server...");
+
+    let config = EngineConfig::from_env();
+    // Searches run their main thread on the thread that takes the request and borrow helpers from here
+    let search_pool = search_pool(config.threads).expect("couldn't start the search threads");
+
+    let training_data = TrainingDataWriter::new(&config);
+    let replays = ReplayWriter::new(&config);
+    let state = Arc::new(SharedState {
+        games: RwLock::new(BTreeMap::new()),
+        config,
+        search_pool,
+        metrics: Metrics::new(),
+        started: Instant::now(),
+        training_data,
+        replays,
+    });
+
+    // Games whose `/end` never came would stay forever otherwise
+    let reaper_state = Arc::clone(&state);
+    thread::Builder::new()
+        .name("game-reaper".to_string())
+        .spawn(move || loop {
+            thread::sleep(reap_interval(&reaper_state.config));
+            evict_idle_games(&reaper_state);
+        })
+        .expect("couldn't start the game reaper");
+
+    rocket::build()
+        .manage(state)
+        .attach(AdHoc::on_response("Server ID Middleware", |_, res| {
+            Box::pin(async move {
+                res.set_raw_header("Server", "battlesnake/github/starter-snake-rust");
+            })
+        }))
+        .mount(
+            "/",
+            routes![handle_index, handle_start, handle_move, handle_end, handle_analyze, handle_metrics],
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::game_recorder::TrainingDataFormat;
+    use super::*;
+
+    fn config() -> EngineConfig {
+        EngineConfig {
+            threads: 1,
+            tt_size_mb: 1,
+            max_depth: 2,
+            training_data_format: TrainingDataFormat::Off,
+            record_replays: false,
+            print_searches: false,
+            ..EngineConfig::default()
+        }
+    }
+
+    // A two snake position with our head in the corner
+    fn request(game_id: &str) -> JsonGameState {
+        let you = json!({
+            "id": "you", "name": "you", "health": 90, "latency": "", "length": 3,
+            "head": { "x": 0, "y": 0 },
+            "body": [{ "x": 0, "y": 0 }, { "x": 1, "y": 0 }, { "x": 2, "y": 0 }],
+        });
+        serde_json::from_value(json!({
+            "game": { "id": game_id, "ruleset": { "name": "standard" }, "timeout": 500 },
+            "turn": 5,
+            "you": you,
+            "board": {
+                "width": 7, "height": 7, "food": [{ "x": 3, "y": 3 }], "hazards": [],
+                "snakes": [you, {
+                    "id": "them", "name": "them", "health": 90, "latency": "", "length": 3,
+                    "head": { "x": 6, "y": 6 },
+                    "body": [{ "x": 6, "y": 6 }, { "x": 5, "y": 6 }, { "x": 4, "y": 6 }],
+                }],
+            },
+        })).unwrap()
+    }
+
+    fn shared_state() -> SharedState {
+        let config = config();
+        SharedState {
+            games: RwLock::new(BTreeMap::new()),
+            search_pool: search_pool(config.threads).unwrap(),
+            metrics: Metrics::new(),
+            started: Instant::now(),
+            training_data: TrainingDataWriter::new(&config),
+            replays: ReplayWriter::new(&config),
+            config,
+        }
+    }
+
+    // Games by id, each last used at the given time
+    fn games(last_used: &[(&str, u64)]) -> Games {
+        let config = config();
+        last_used.iter()
+            .map(|&(id, now_ms)| {
+                let slot = GameSlot::new(GameState::new(&request(id), &config), now_ms);
+                (id.to_string(), Arc::new(slot))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_least_recently_used_game_makes_room() {
+        let mut games = games(&[("a", 300), ("b", 100), ("c", 200)]);
+
+        assert_eq!(least_recently_used(&mut games, 4), Vec::<String>::new());
+        assert_eq!(least_recently_used(&mut games, 3), vec!["b"]);
+        assert_eq!(games.keys().collect::<Vec<_>>(), vec!["a", "c"]);
+        assert_eq!(least_recently_used(&mut games, 1), vec!["c", "a"]);
+        assert!(games.is_empty());
+    }
+
+    #[test]
+    fn only_games_idle_past_the_timeout_are_reaped() {
+        let mut games = games(&[("recent", 9_000), ("on_the_edge", 5_000), ("idle", 4_999), ("ancient", 0)]);
+
+        assert_eq!(idle_games(&mut games, 10_000, 5_000), vec!["ancient", "idle"]);
+        assert_eq!(games.keys().collect::<Vec<_>>(), vec!["on_the_edge", "recent"]);
+        // A game used after the reaper's clock was read isn't idle
+        assert_eq!(idle_games(&mut games, 8_000, 5_000), Vec::<String>::new());
+    }
+
+    #[test]
+    fn a_poisoned_game_is_rebuilt_and_still_answered() {
+        let state = shared_state();
+        let req = request("poisoned");
+        let slot = Arc::new(GameSlot::new(GameState::new(&req, &state.config), state.now_ms()));
+        write_games(&state, "-").insert("poisoned".to_string(), Arc::clone(&slot));
+
+        let poisoner = Arc::clone(&slot);
+        let panicked = thread::spawn(move || {
+            let mut game = poisoner.state.lock().unwrap();
+            game.stats.moves = 50;
+            panic!("poisoning the game");
+        }).join();
+        assert!(panicked.is_err() && slot.state.is_poisoned());
+
+        // Our head is in the corner with our neck to the right, up is the only way out
+        let response = make_move(&req, &state, Instant::now());
+        assert_eq!(response["move"], "up");
+
+        assert!(!slot.state.is_poisoned());
+        // Started over before the search, which is the only one it has seen
+        assert_eq!(slot.state.lock().unwrap().stats.moves, 1);
+        assert!(state.metrics.render(1, 0).contains("battlesnake_incidents_total{kind=\"poisoned_lock\"} 1\n"));
+    }
+}
@@ -0,0 +1,114 @@
+use std::ops::Index;
+use crate::{Battlesnake, Coord};
+
+// A snake the way the board keeps it during search. Everything that only matters to the API
+// (id, name, ...) stays with the board, a snake is known by its index in `GameBoard::snakes`.
+#[derive(Debug, Clone)]
+pub struct Snake {
+    pub(crate) index: usize,
+    pub(crate) body: Body,
+    pub(crate) health: i32,
+    pub(crate) length: i32,
+}
+
+impl Snake {
+    // `capacity` is the longest the body can ever get, see `Body`
+    pub fn from_battlesnake(index: usize, snake: &Battlesnake, capacity: usize) -> Self {
+        let mut body = Body::with_capacity(capacity.max(snake.body.len() + 1));
+        for coord in &snake.body {
+            body.push_back(*coord);
+        }
+
+        Self {
+            index,
+            body,
+            health: snake.health,
+            length: snake.length,
+        }
+    }
+
+    pub fn head(&self) -> Coord {
+        self.body.front()
+    }
+
+    pub fn tail(&self) -> Coord {
+        self.body.back()
+    }
+}
+
+// Head first, in a ring buffer that is sized once and never reallocates. Moving a snake pushes
+// a head and pops a tail, and eating stacks one more copy of the tail, so a body never gets
+// longer than the board has cells plus the couple of stacked segments at its end.
+#[derive(Debug, Clone)]
+pub struct Body {
+    cells: Box<[Coord]>,
+    start: usize,
+    len: usize,
+}
+
+impl Body {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            cells: vec![Coord { x: 0, y: 0 }; capacity].into_boxed_slice(),
+            start: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn front(&self) -> Coord {
+        self[0]
+    }
+
+    pub fn back(&self) -> Coord {
+        self[self.len - 1]
+    }
+
+    pub fn push_front(&mut self, coord: Coord) {
+        assert!(self.len < self.cells.len(), "snake body is over capacity");
+        self.start = (self.start + self.cells.len() - 1) % self.cells.len();
+        self.cells[self.start] = coord;
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Coord {
+        let coord = self.front();
+        self.start = (self.start + 1) % self.cells.len();
+        self.len -= 1;
+        coord
+    }
+
+    pub fn push_back(&mut self, coord: Coord) {
+        assert!(self.len < self.cells.len(), "snake body is over capacity");
+        let end = (self.start + self.len) % self.cells.len();
+        self.cells[end] = coord;
+        self.len += 1;
+    }
+
+    pub fn pop_back(&mut self) -> Coord {
+        let coord = self.back();
+        self.len -= 1;
+        coord
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Coord> + '_ {
+        (0..self.len).map(move |i| self[i])
+    }
+}
+
+impl Index<usize> for Body {
+    type Output = Coord;
+
+    fn index(&self, i: usize) -> &Coord {
+        debug_assert!(i < self.len);
+        &self.cells[(self.start + i) % self.cells.len()]
+    }
+}
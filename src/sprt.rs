@@ -0,0 +1,417 @@
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use rayon::ThreadPoolBuilder;
+use rayon::prelude::*;
+use crate::arena::{self, ArenaSettings, Engine, GameOutcome};
+use crate::cli::value;
+use crate::search::{search_pool, SearchPool};
+
+pub const USAGE: &str = "\
+Usage: sprt [--base SPEC] [--test SPEC] [--elo0 N] [--elo1 N] [--alpha N] [--beta N]
+            [--max-games N] [--concurrency N] [--replays] [arena options]...
+
+Plays a test engine against a base engine until a sequential probability ratio test decides
+whether the test engine is stronger, and reports the Elo difference as it goes.
+
+  --base SPEC       NAME or NAME:KEY=VALUE,..., the engine as it is, `base` by default
+  --test SPEC       the engine with the change, `test` by default
+  --elo0 N          Elo gain of the null hypothesis, 0 by default
+  --elo1 N          Elo gain of the alternative hypothesis, 5 by default
+  --alpha N         chance of accepting the alternative when the null is true, 0.05 by default
+  --beta N          chance of accepting the null when the alternative is true, 0.05 by default
+  --max-games N     games after which the test gives up undecided, 20000 by default
+  --concurrency N   games played at once, every core by default
+  --replays         keep a replay of every game in the output directory (`sprt` by default)
+
+Games are played in pairs from the same starting position with the seats swapped, and the
+arena's --width, --height, --mode, --time, --seed, --max-turns, --threads, --out and --openings
+set them up. Engines search with one thread unless --threads says otherwise.";
+
+// The hypotheses of a test and the error rates it accepts them with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub(crate) elo0: f64,
+    pub(crate) elo1: f64,
+    pub(crate) alpha: f64,
+    pub(crate) beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Self { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 }
+    }
+}
+
+// What a test has concluded so far
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    // The test engine is no stronger than elo0
+    H0,
+    // The test engine is at least elo1 stronger
+    H1,
+    Undecided,
+}
+
+impl Sprt {
+    // The log likelihood ratio accepts H0 at or below the first and H1 at or above the second
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    // The generalized SPRT's log likelihood ratio of H1 against H0, with the score of a pair
+    // taken as normally distributed
+    // See Michel Van den Bergh, "A practical introduction to the GSPRT"
+    pub fn llr(&self, pairs: &Pentanomial) -> f64 {
+        let (mean, variance) = match pairs.mean_variance() {
+            Some((mean, variance)) if variance > 0.0 => (mean, variance),
+            _ => return 0.0,
+        };
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+
+        pairs.pairs() as f64 * (score1 - score0) * (2.0 * mean - score0 - score1) / (2.0 * variance)
+    }
+
+    pub fn verdict(&self, pairs: &Pentanomial) -> Verdict {
+        let llr = self.llr(pairs);
+        let (lower, upper) = self.bounds();
+
+        if llr >= upper {
+            Verdict::H1
+        } else if llr <= lower {
+            Verdict::H0
+        } else {
+            Verdict::Undecided
+        }
+    }
+}
+
+// How many pairs of games the test engine scored 0, 0.5, 1, 1.5 and 2 points in. Pairs start from
+// the same position with the seats swapped, so they cancel out most of what the position itself
+// is worth.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Pentanomial {
+    pub(crate) counts: [u64; 5],
+}
+
+impl Pentanomial {
+    // `points` is what the test engine scored in both games together
+    pub fn add(&mut self, points: f64) {
+        self.counts[(points * 2.0).round().clamp(0.0, 4.0) as usize] += 1;
+    }
+
+    pub fn pairs(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    // Mean and variance of the score of a pair, as a share of the points there were to get
+    fn mean_variance(&self) -> Option<(f64, f64)> {
+        let pairs = self.pairs() as f64;
+        if pairs == 0.0 {
+            return None;
+        }
+
+        let scores = self.counts.iter().enumerate().map(|(i, count)| (i as f64 / 4.0, *count as f64));
+        let mean = scores.clone().map(|(score, count)| score * count).sum::<f64>() / pairs;
+        let variance = scores.map(|(score, count)| (score - mean).powi(2) * count).sum::<f64>() / pairs;
+        Some((mean, variance))
+    }
+
+    // Elo difference the results so far point to and the half width of its 95% confidence interval
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let (mean, variance) = self.mean_variance()?;
+        let margin = 1.96 * (variance / self.pairs() as f64).sqrt();
+
+        let elo = elo_difference(mean);
+        let error = (elo_difference(mean + margin) - elo_difference(mean - margin)) / 2.0;
+        Some((elo, error))
+    }
+}
+
+// The score the logistic model expects of a player `elo` stronger than its opponent
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// The inverse of `expected_score`, kept finite for a score of 0 or 1
+fn elo_difference(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+// Every game of a test so far, from the test engine's side
+#[derive(Debug, Clone, Default)]
+struct Tally {
+    pairs: Pentanomial,
+    wins: u64,
+    draws: u64,
+    losses: u64,
+}
+
+impl Tally {
+    fn games(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+
+    fn record(&mut self, pair: &[GameOutcome; 2]) {
+        let mut pair_points = 0.0;
+        for outcome in pair {
+            let points = outcome.points(TEST).unwrap_or(0.5);
+            pair_points += points;
+            if points == 1.0 {
+                self.wins += 1;
+            } else if points == 0.0 {
+                self.losses += 1;
+            } else {
+                self.draws += 1;
+            }
+        }
+        self.pairs.add(pair_points);
+    }
+
+    fn report(&self, sprt: &Sprt) -> String {
+        let (lower, upper) = sprt.bounds();
+        let elo = match self.pairs.elo() {
+            Some((elo, error)) => format!("Elo {:+.1} ± {:.1}", elo, error),
+            None => "Elo -".to_string(),
+        };
+
+        format!("Games {} (+{} ={} -{}), pairs {:?}, {}, LLR {:.2} [{:.2}, {:.2}]",
+                self.games(), self.wins, self.draws, self.losses, self.pairs.counts, elo,
+                sprt.llr(&self.pairs), lower, upper)
+    }
+}
+
+// Indices of the engines in every game
+const BASE: usize = 0;
+const TEST: usize = 1;
+
+// Runs the `sprt` binary on its command line, without the program name
+pub fn run(args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let options = Options::parse(args).map_err(|e| format!("{}, see --help", e))?;
+    let (sprt, settings) = (options.sprt, &options.settings);
+
+    let mut engines = [options.base, options.test];
+    for engine in &mut engines {
+        arena::prepare(engine, settings);
+    }
+
+    // Games are searched one thread each by default, the cores go to playing many at once
+    let games_pool = ThreadPoolBuilder::new()
+        .num_threads(options.concurrency)
+        .thread_name(|i| format!("game-{}", i))
+        .build()?;
+    // Helpers for the searches of every game thread, each only ever plays one game at a time
+    let search_pools = (0..options.concurrency)
+        .map(|_| search_pool(settings.threads))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    println!("{} against {}, {} games at once {}, {}ms a move, seed {}",
+             engines[TEST].name, engines[BASE].name, options.concurrency, settings.describe_games(),
+             settings.time_ms, settings.seed);
+    println!("H0: Elo {:+} H1: Elo {:+}, alpha {} beta {}", sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta);
+
+    let mut tally = Tally::default();
+    let mut next_pair = 0;
+    let verdict = loop {
+        let verdict = sprt.verdict(&tally.pairs);
+        if verdict != Verdict::Undecided || tally.games() >= options.max_games {
+            break verdict;
+        }
+
+        // A batch is one pair for every game slot, the test can only stop in between batches
+        let batch = (options.concurrency as u64).min((options.max_games - tally.games()).div_ceil(2)).max(1);
+        let pairs: Vec<Result<[GameOutcome; 2], String>> = games_pool.install(|| {
+            (next_pair..next_pair + batch).into_par_iter()
+                .map(|pair| {
+                    let search_pool = &search_pools[rayon::current_thread_index().unwrap_or(0)];
+                    play_pair(settings, &engines, pair, search_pool)
+                })
+                .collect()
+        });
+        next_pair += batch;
+
+        for pair in pairs {
+            tally.record(&pair?);
+        }
+        println!("{}", tally.report(&sprt));
+    };
+
+    let mut report = String::new();
+    writeln!(report).unwrap();
+    writeln!(report, "{}", tally.report(&sprt)).unwrap();
+    match verdict {
+        Verdict::H1 => writeln!(report, "H1 accepted: {} is at least {:+} Elo stronger than {}",
+                                engines[TEST].name, sprt.elo1, engines[BASE].name),
+        Verdict::H0 => writeln!(report, "H0 accepted: {} is no more than {:+} Elo stronger than {}",
+                                engines[TEST].name, sprt.elo0, engines[BASE].name),
+        Verdict::Undecided => writeln!(report, "No decision after {} games", tally.games()),
+    }.unwrap();
+    print!("{}", report);
+
+    if settings.replays {
+        fs::create_dir_all(&settings.out)?;
+        fs::write(settings.out.join("sprt.txt"), report.trim_start())?;
+    }
+
+    Ok(())
+}
+
+// Both games of pair `pair`, which start from the same position with the engines' seats swapped.
+// `search_pool` is the game thread's own, so games played at once never wait on each other's helpers.
+fn play_pair(settings: &ArenaSettings, engines: &[Engine; 2], pair: u64,
+             search_pool: &SearchPool) -> Result<[GameOutcome; 2], String> {
+    let play = |game: u64| {
+        let (start, seats, mut rng) = arena::setup(settings, engines.len(), game);
+        let game_id = format!("sprt-{}-{}", settings.seed, game + 1);
+        arena::play_game(settings, engines, &game_id, &start, &seats, &mut rng, search_pool)
+            .map_err(|e| format!("{}: {}", game_id, e))
+    };
+
+    Ok([play(pair * 2)?, play(pair * 2 + 1)?])
+}
+
+// Everything a test is run with
+struct Options {
+    base: Engine,
+    test: Engine,
+    sprt: Sprt,
+    max_games: u64,
+    concurrency: usize,
+    settings: ArenaSettings,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut settings = ArenaSettings {
+            snakes: 2,
+            out: PathBuf::from("sprt"),
+            replays: false,
+            ..ArenaSettings::default()
+        };
+        let (mut base, mut test) = (None, None);
+        let mut sprt = Sprt::default();
+        let mut max_games = 20000;
+        let mut concurrency = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--base" => base = Some(Engine::parse(&value::<String>(&arg, args.next())?)?),
+                "--test" => test = Some(Engine::parse(&value::<String>(&arg, args.next())?)?),
+                "--elo0" => sprt.elo0 = value(&arg, args.next())?,
+                "--elo1" => sprt.elo1 = value(&arg, args.next())?,
+                "--alpha" => sprt.alpha = value(&arg, args.next())?,
+                "--beta" => sprt.beta = value(&arg, args.next())?,
+                "--max-games" => max_games = value::<u64>(&arg, args.next())?.max(2),
+                "--concurrency" => concurrency = value::<usize>(&arg, args.next())?.max(1),
+                "--replays" => settings.replays = true,
+                _ if settings.parse_option(&arg, &mut args)? => {}
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+
+        settings.check()?;
+        // Pairs swap the seats of exactly two engines
+        if settings.snakes != 2 {
+            return Err(format!("games are between two snakes, not {}", settings.snakes));
+        }
+        if let Some(opening) = settings.openings.iter().find(|opening| opening.snakes.len() != 2) {
+            return Err(format!("games are between two snakes, an opening has {}", opening.snakes.len()));
+        }
+        if sprt.elo1 <= sprt.elo0 {
+            return Err(format!("elo1 ({}) has to be above elo0 ({})", sprt.elo1, sprt.elo0));
+        }
+        if [sprt.alpha, sprt.beta].iter().any(|rate| *rate <= 0.0 || *rate >= 0.5) {
+            return Err("alpha and beta have to be between 0 and 0.5".to_string());
+        }
+
+        Ok(Self {
+            base: base.map_or_else(|| Engine::parse("base"), Ok)?,
+            test: test.map_or_else(|| Engine::parse("test"), Ok)?,
+            sprt,
+            max_games,
+            concurrency,
+            settings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(counts: [u64; 5]) -> Pentanomial {
+        Pentanomial { counts }
+    }
+
+    #[test]
+    fn scores_and_elo_convert_both_ways() {
+        assert_eq!(expected_score(0.0), 0.5);
+        assert!((elo_difference(0.75) - 400.0 * 3f64.log10()).abs() < 1e-9);
+        for &elo in &[-300.0, -5.0, 0.0, 5.0, 300.0] {
+            assert!((elo_difference(expected_score(elo)) - elo).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn bounds_follow_the_error_rates() {
+        let (lower, upper) = Sprt::default().bounds();
+        assert!((lower - (0.05f64 / 0.95).ln()).abs() < 1e-12);
+        assert!((upper - (0.95f64 / 0.05).ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn nothing_but_draws_is_even() {
+        let draws = pairs([0, 0, 500, 0, 0]);
+
+        assert_eq!(draws.elo(), Some((0.0, 0.0)));
+        assert_eq!(Sprt::default().llr(&draws), 0.0);
+        assert_eq!(Sprt::default().verdict(&draws), Verdict::Undecided);
+        assert_eq!(Pentanomial::default().elo(), None);
+    }
+
+    #[test]
+    fn symmetric_results_favour_neither_side() {
+        let symmetric = pairs([30, 70, 200, 70, 30]);
+        let (elo, error) = symmetric.elo().unwrap();
+        assert!(elo.abs() < 1e-9);
+        assert!(error > 0.0);
+
+        // Halfway between hypotheses as far from even as each other
+        let sprt = Sprt { elo0: -5.0, elo1: 5.0, ..Sprt::default() };
+        assert!(sprt.llr(&symmetric).abs() < 1e-9);
+        // And leaning towards H0 when that is the even one
+        assert!(Sprt::default().llr(&symmetric) < 0.0);
+    }
+
+    #[test]
+    fn lopsided_results_cross_the_bounds() {
+        let sprt = Sprt::default();
+        let (lower, upper) = sprt.bounds();
+
+        let better = pairs([0, 0, 100, 300, 0]);
+        assert!(better.elo().unwrap().0 > 100.0);
+        assert!(sprt.llr(&better) > upper);
+        assert_eq!(sprt.verdict(&better), Verdict::H1);
+
+        let worse = pairs([0, 300, 100, 0, 0]);
+        assert!(worse.elo().unwrap().0 < -100.0);
+        assert!(sprt.llr(&worse) < lower);
+        assert_eq!(sprt.verdict(&worse), Verdict::H0);
+
+        let few = pairs([0, 0, 3, 1, 0]);
+        assert_eq!(sprt.verdict(&few), Verdict::Undecided);
+    }
+
+    #[test]
+    fn only_two_snakes_play() {
+        let parse = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+
+        assert!(parse(&[]).is_ok());
+        assert!(parse(&["--snakes", "2"]).is_ok());
+        assert_eq!(parse(&["--snakes", "4"]).err(), Some("games are between two snakes, not 4".to_string()));
+        assert!(parse(&["--snakes", "1"]).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use std::time::Duration;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+// Cutoffs are counted by the index of the move that caused them, anything past the last
+// bucket goes in it
+const CUTOFF_BUCKETS: usize = 4;
+
+// What a search thread counts as it goes. Every thread has its own, `think` adds them up.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchCounters {
+    pub(crate) nodes: u64,
+    // Deepest ply any node was searched at, extensions included
+    pub(crate) seldepth: i32,
+    pub(crate) tt_probes: u64,
+    pub(crate) tt_hits: u64,
+    pub(crate) cutoffs: [u64; CUTOFF_BUCKETS],
+    pub(crate) nmp_tries: u64,
+    pub(crate) nmp_cutoffs: u64,
+}
+
+impl SearchCounters {
+    pub fn add(&mut self, other: &SearchCounters) {
+        self.nodes += other.nodes;
+        self.seldepth = self.seldepth.max(other.seldepth);
+        self.tt_probes += other.tt_probes;
+        self.tt_hits += other.tt_hits;
+        for (cutoffs, other) in self.cutoffs.iter_mut().zip(other.cutoffs.iter()) {
+            *cutoffs += other;
+        }
+        self.nmp_tries += other.nmp_tries;
+        self.nmp_cutoffs += other.nmp_cutoffs;
+    }
+
+    pub fn cutoff(&mut self, move_index: usize) {
+        self.cutoffs[move_index.min(CUTOFF_BUCKETS - 1)] += 1;
+    }
+
+    fn to_json(self) -> Value {
+        let total_cutoffs: u64 = self.cutoffs.iter().sum();
+
+        json!({
+            "nodes": self.nodes,
+            "seldepth": self.seldepth,
+            "tt_hit_rate": rate(self.tt_hits, self.tt_probes),
+            "cutoffs": total_cutoffs,
+            "cutoff_rates": self.cutoffs.iter().map(|cutoffs| rate(*cutoffs, total_cutoffs)).collect::<Vec<_>>(),
+            "nmp_tries": self.nmp_tries,
+            "nmp_success_rate": rate(self.nmp_cutoffs, self.nmp_tries),
+        })
+    }
+}
+
+// One completed iteration of the main thread
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct IterationStats {
+    pub(crate) depth: i32,
+    pub(crate) nodes: u64,
+    pub(crate) time_ms: f64,
+}
+
+// What the search of one move did, every thread together
+#[derive(Debug, Clone, Default)]
+pub struct SearchStats {
+    pub(crate) counters: SearchCounters,
+    pub(crate) threads: usize,
+    pub(crate) depth: i32,
+    pub(crate) time: Duration,
+    // Whether the hard deadline cut the last iteration off
+    pub(crate) aborted: bool,
+    pub(crate) iterations: Vec<IterationStats>,
+}
+
+impl SearchStats {
+    pub fn nps(&self) -> u64 {
+        nps(self.counters.nodes, self.time)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = self.counters.to_json();
+        value["threads"] = json!(self.threads);
+        value["depth"] = json!(self.depth);
+        value["time_ms"] = json!(self.time.as_secs_f64() * 1000.0);
+        value["nps"] = json!(self.nps());
+        value["aborted"] = json!(self.aborted);
+        value["iterations"] = json!(self.iterations);
+        value
+    }
+}
+
+// Every move of a game added up, reported when the game ends
+#[derive(Debug, Clone, Default)]
+pub struct GameStats {
+    pub(crate) moves: u32,
+    pub(crate) counters: SearchCounters,
+    pub(crate) time: Duration,
+    pub(crate) depth_sum: i64,
+    pub(crate) min_depth: Option<i32>,
+    pub(crate) max_depth: i32,
+}
+
+impl GameStats {
+    pub fn record(&mut self, stats: &SearchStats) {
+        self.moves += 1;
+        self.counters.add(&stats.counters);
+        self.time += stats.time;
+        self.depth_sum += stats.depth as i64;
+        self.min_depth = Some(self.min_depth.map_or(stats.depth, |depth| depth.min(stats.depth)));
+        self.max_depth = self.max_depth.max(stats.depth);
+    }
+
+    // Another game's numbers on top of these
+    pub fn add(&mut self, other: &GameStats) {
+        self.moves += other.moves;
+        self.counters.add(&other.counters);
+        self.time += other.time;
+        self.depth_sum += other.depth_sum;
+        self.min_depth = match (self.min_depth, other.min_depth) {
+            (Some(depth), Some(other)) => Some(depth.min(other)),
+            (depth, other) => depth.or(other),
+        };
+        self.max_depth = self.max_depth.max(other.max_depth);
+    }
+
+    pub fn nps(&self) -> u64 {
+        nps(self.counters.nodes, self.time)
+    }
+
+    pub fn average_depth(&self) -> f64 {
+        if self.moves > 0 { self.depth_sum as f64 / self.moves as f64 } else { 0.0 }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut value = self.counters.to_json();
+        value["moves"] = json!(self.moves);
+        value["time_ms"] = json!(self.time.as_secs_f64() * 1000.0);
+        value["nps"] = json!(self.nps());
+        value["average_depth"] = json!(self.average_depth());
+        value["min_depth"] = json!(self.min_depth.unwrap_or(0));
+        value["max_depth"] = json!(self.max_depth);
+        value
+    }
+}
+
+fn rate(part: u64, whole: u64) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 / whole as f64 }
+}
+
+fn nps(nodes: u64, time: Duration) -> u64 {
+    let seconds = time.as_secs_f64();
+    if seconds > 0.0 { (nodes as f64 / seconds) as u64 } else { 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(depth: i32, nodes: u64, time_ms: u64) -> SearchStats {
+        SearchStats {
+            counters: SearchCounters { nodes, tt_probes: 4, tt_hits: 1, ..SearchCounters::default() },
+            threads: 1,
+            depth,
+            time: Duration::from_millis(time_ms),
+            ..SearchStats::default()
+        }
+    }
+
+    #[test]
+    fn recording_searches_tracks_the_depth_range() {
+        let mut game = GameStats::default();
+        game.record(&search(7, 1000, 100));
+        game.record(&search(4, 3000, 100));
+        game.record(&search(9, 2000, 300));
+
+        assert_eq!(game.moves, 3);
+        assert_eq!(game.counters.nodes, 6000);
+        assert_eq!(game.time, Duration::from_millis(500));
+        assert_eq!((game.min_depth, game.max_depth), (Some(4), 9));
+        assert_eq!(game.average_depth(), 20.0 / 3.0);
+        assert_eq!(game.nps(), 12000);
+    }
+
+    #[test]
+    fn adding_games_merges_the_depth_range() {
+        let mut total = GameStats::default();
+        let mut shallow = GameStats::default();
+        shallow.record(&search(3, 100, 10));
+        shallow.record(&search(5, 100, 10));
+        let mut deep = GameStats::default();
+        deep.record(&search(8, 100, 10));
+
+        // A game without moves doesn't pull the minimum down to 0
+        total.add(&GameStats::default());
+        assert_eq!(total.min_depth, None);
+        total.add(&deep);
+        assert_eq!((total.min_depth, total.max_depth), (Some(8), 8));
+        total.add(&shallow);
+        assert_eq!((total.min_depth, total.max_depth), (Some(3), 8));
+        total.add(&GameStats::default());
+        assert_eq!((total.min_depth, total.max_depth), (Some(3), 8));
+
+        assert_eq!(total.moves, 3);
+        assert_eq!(total.counters.tt_probes, 12);
+        assert_eq!(total.depth_sum, 16);
+    }
+
+    #[test]
+    fn rates_are_zero_without_nodes_or_time() {
+        assert_eq!(search(1, 0, 100).nps(), 0);
+        assert_eq!(search(1, 500, 0).nps(), 0);
+        assert_eq!(GameStats::default().nps(), 0);
+        assert_eq!(GameStats::default().average_depth(), 0.0);
+
+        let empty = SearchCounters::default().to_json();
+        assert_eq!(empty["tt_hit_rate"], 0.0);
+        assert_eq!(empty["nmp_success_rate"], 0.0);
+        assert_eq!(empty["cutoff_rates"], json!([0.0, 0.0, 0.0, 0.0]));
+        assert_eq!(search(1, 10, 1).counters.to_json()["tt_hit_rate"], 0.25);
+        assert_eq!(GameStats::default().to_json()["min_depth"], 0);
+    }
+}
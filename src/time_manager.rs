@@ -0,0 +1,185 @@
+use std::time::{Duration, Instant};
+use crate::board::Direction;
+use crate::config::EngineConfig;
+
+// Round trip overhead we assume before the engine has told us our latency once
+const DEFAULT_NETWORK_OVERHEAD_MS: u64 = 80;
+// Even when the numbers say we are already late, search at least this long
+const MIN_BUDGET_MS: u64 = 10;
+
+// Keeps an estimate of how much of the engine's timeout is lost outside of our handler.
+// The engine reports the full round trip of our previous response in `you.latency`, so
+// whatever is left after subtracting our own response time is network and queueing.
+pub struct LatencyTracker {
+    network_overhead_ms: u64,
+    last_response_ms: Option<u64>,
+}
+
+impl Default for LatencyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self {
+            network_overhead_ms: DEFAULT_NETWORK_OVERHEAD_MS,
+            last_response_ms: None,
+        }
+    }
+
+    // `reported_latency` is the latency the engine measured for our previous /move response
+    pub fn observe(&mut self, reported_latency: &str) {
+        let latency = match reported_latency.trim().parse::<u64>() {
+            Ok(latency) if latency > 0 => latency,
+            _ => return,
+        };
+
+        if let Some(last_response_ms) = self.last_response_ms {
+            let overhead = latency.saturating_sub(last_response_ms);
+
+            // Jump straight up on a spike, but only decay slowly so one quick round trip
+            // doesn't talk us into a budget we can't afford
+            self.network_overhead_ms = if overhead > self.network_overhead_ms {
+                overhead
+            } else {
+                (self.network_overhead_ms * 3 + overhead) / 4
+            };
+        }
+    }
+
+    pub fn record_response(&mut self, elapsed: Duration) {
+        self.last_response_ms = Some(elapsed.as_millis() as u64);
+    }
+
+    pub fn network_overhead_ms(&self) -> u64 {
+        self.network_overhead_ms
+    }
+}
+
+// Splits a move's budget into a soft deadline, after which no new iteration is started,
+// and a hard deadline, at which the running iteration is aborted
+pub struct TimeManager {
+    start_time: Instant,
+    soft_limit: Duration,
+    hard_limit: Duration,
+    last_best_move: Direction,
+    stable_iterations: u32,
+}
+
+impl TimeManager {
+    // `start_time` should be taken when the request arrived, not when the search starts,
+    // so that parsing and board setup are paid for out of the same budget
+    pub fn new(start_time: Instant, timeout_ms: u32, network_overhead_ms: u64, config: &EngineConfig) -> Self {
+        let hard_ms = (timeout_ms as u64)
+            .saturating_sub(network_overhead_ms)
+            .saturating_sub(config.safety_margin_ms)
+            .max(MIN_BUDGET_MS);
+        let soft_ms = hard_ms * config.soft_deadline_percent / 100;
+
+        Self {
+            start_time,
+            soft_limit: Duration::from_millis(soft_ms),
+            hard_limit: Duration::from_millis(hard_ms),
+            last_best_move: Direction::None,
+            stable_iterations: 0,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.start_time.elapsed()
+    }
+
+    pub fn hard_limit(&self) -> Duration {
+        self.hard_limit
+    }
+
+    // When the hard limit is reached, search threads only need to look at the clock for that
+    pub fn hard_deadline(&self) -> Instant {
+        self.start_time + self.hard_limit
+    }
+
+    // Called after every completed iteration, returns true if the next one shouldn't be started.
+    // The longer the best move has stayed the same, the earlier we are willing to stop.
+    pub fn should_stop(&mut self, best_move: Direction) -> bool {
+        if best_move == self.last_best_move {
+            self.stable_iterations += 1;
+        } else {
+            self.stable_iterations = 0;
+            self.last_best_move = best_move;
+        }
+
+        let percent = match self.stable_iterations {
+            0 => 100,
+            1 => 85,
+            2 => 70,
+            _ => 50,
+        };
+
+        self.start_time.elapsed() >= self.soft_limit * percent / 100
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 50% soft deadline and 60ms held back, whatever the environment says
+    fn config() -> EngineConfig {
+        EngineConfig { safety_margin_ms: 60, soft_deadline_percent: 50, ..EngineConfig::default() }
+    }
+
+    #[test]
+    fn limits_leave_out_the_overhead_and_the_margin() {
+        let time_manager = TimeManager::new(Instant::now(), 500, 80, &config());
+
+        assert_eq!(time_manager.hard_limit, Duration::from_millis(360));
+        assert_eq!(time_manager.soft_limit, Duration::from_millis(180));
+    }
+
+    #[test]
+    fn late_moves_still_get_the_minimum_budget() {
+        let time_manager = TimeManager::new(Instant::now(), 100, 500, &config());
+
+        assert_eq!(time_manager.hard_limit, Duration::from_millis(MIN_BUDGET_MS));
+        assert_eq!(time_manager.soft_limit, Duration::from_millis(MIN_BUDGET_MS / 2));
+    }
+
+    #[test]
+    fn a_stable_best_move_stops_the_search_earlier() {
+        // A 1000ms soft limit, 600ms of it gone
+        let start_time = Instant::now() - Duration::from_millis(600);
+        let mut time_manager = TimeManager::new(start_time, 2140, 80, &config());
+
+        // 100%, 85% and 70% of the soft limit are still ahead, 50% isn't
+        assert!(!time_manager.should_stop(Direction::Up));
+        assert!(!time_manager.should_stop(Direction::Up));
+        assert!(!time_manager.should_stop(Direction::Up));
+        assert!(time_manager.should_stop(Direction::Up));
+
+        // A new best move starts the count over
+        assert!(!time_manager.should_stop(Direction::Left));
+    }
+
+    #[test]
+    fn overhead_jumps_up_and_decays_slowly() {
+        let mut tracker = LatencyTracker::new();
+
+        // Nothing to subtract our response time from yet
+        tracker.observe("150");
+        assert_eq!(tracker.network_overhead_ms(), DEFAULT_NETWORK_OVERHEAD_MS);
+
+        tracker.record_response(Duration::from_millis(20));
+        tracker.observe("150");
+        assert_eq!(tracker.network_overhead_ms(), 130);
+
+        tracker.observe("60");
+        assert_eq!(tracker.network_overhead_ms(), (130 * 3 + 40) / 4);
+
+        // Timed out or missing latencies say nothing
+        tracker.observe("");
+        tracker.observe("0");
+        assert_eq!(tracker.network_overhead_ms(), (130 * 3 + 40) / 4);
+    }
+}
@@ -1,22 +1,315 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use crate::board::Direction;
 
-#[derive(Clone, Copy)]
+// Entries a position can go in, 64 bytes worth
+const BUCKET_SIZE: usize = 4;
+// How many entries `hashfull` samples, the way UCI engines report it
+const HASHFULL_SAMPLE: usize = 1000;
+
+// What an entry's score says about the position: every move failed low and the real score is at
+// most this, one cut off and it's at least this, or it's the score
+pub const UPPER: i32 = 1;
+pub const LOWER: i32 = 2;
+pub const EXACT: i32 = 3;
+
+// What the search keeps about a position, as it comes out of the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TTEntry {
-    pub(crate) zobrist: u64,
     pub(crate) best_move: Direction,
     pub(crate) second_best_move: Direction,
     pub(crate) worst_move: Direction,
 
-    // A bit of a hack to reduce collisions
-    pub(crate) friendly_health: i32,
-    pub(crate) enemy_health: i32,
-    pub(crate) snake_head_x: i32,
-    pub(crate) snake_head_y: i32,
-    pub(crate) enemy_head_x: i32,
-    pub(crate) enemy_head_y: i32,
-
     pub(crate) depth: i32,
     pub(crate) score: i32,
+    // `UPPER`, `LOWER` or `EXACT`
     pub(crate) flag: i32,
 }
 
+// What a probe that found nothing hands back
+impl Default for TTEntry {
+    fn default() -> Self {
+        Self {
+            best_move: Direction::None,
+            second_best_move: Direction::None,
+            worst_move: Direction::None,
+            depth: -1,
+            score: -1,
+            flag: -1,
+        }
+    }
+}
+
+// One packed 16 byte entry, written and read without locks. The key is stored XORed with the
+// data, so an entry another thread was halfway through writing fails the key check instead of
+// being read torn. Data bits:
+//   0..32  score
+//   32..40 depth
+//   40..42 flag
+//   42..51 best, second best and worst move, 3 bits each
+//   51..59 generation
+//   59     set on every entry that was ever written
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+const USED: u64 = 1 << 59;
+
+// Shared by every search thread of a game and kept from one move to the next. Entries from
+// earlier moves are the first to go, after that the shallowest.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+    // Buckets minus one, there is always a power of two of them
+    mask: usize,
+    // Bumped at the start of every move, only the low 8 bits make it into an entry
+    generation: AtomicU32,
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        let bytes = size_mb.max(1) << 20;
+        let mut buckets = 1;
+        while buckets * 2 * BUCKET_SIZE * std::mem::size_of::<Slot>() <= bytes {
+            buckets *= 2;
+        }
+
+        Self {
+            slots: (0..buckets * BUCKET_SIZE).map(|_| Slot::default()).collect(),
+            mask: buckets - 1,
+            generation: AtomicU32::new(0),
+        }
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.slots.len() * std::mem::size_of::<Slot>()
+    }
+
+    // Call once per move, before the search starts
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn generation(&self) -> u64 {
+        (self.generation.load(Ordering::Relaxed) & 0xFF) as u64
+    }
+
+    fn bucket(&self, key: u64) -> &[Slot] {
+        let start = (key as usize & self.mask) * BUCKET_SIZE;
+        &self.slots[start..start + BUCKET_SIZE]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<TTEntry> {
+        self.bucket(key).iter().find_map(|slot| {
+            let data = slot.data.load(Ordering::Relaxed);
+            if data & USED == 0 || slot.key.load(Ordering::Relaxed) ^ data != key {
+                return None;
+            }
+
+            Some(TTEntry {
+                score: data as u32 as i32,
+                depth: ((data >> 32) & 0xFF) as i32,
+                flag: ((data >> 40) & 0b11) as i32,
+                best_move: unpack_direction(data >> 42),
+                second_best_move: unpack_direction(data >> 45),
+                worst_move: unpack_direction(data >> 48),
+            })
+        })
+    }
+
+    // Goes over the same position if it's in the bucket already, otherwise over whichever entry
+    // is the least worth keeping: old generations first, then shallow searches
+    pub fn store(&self, key: u64, entry: TTEntry) {
+        let generation = self.generation();
+        let data = entry.score as u32 as u64
+            | (entry.depth.clamp(0, 0xFF) as u64) << 32
+            | (entry.flag as u64 & 0b11) << 40
+            | pack_direction(entry.best_move) << 42
+            | pack_direction(entry.second_best_move) << 45
+            | pack_direction(entry.worst_move) << 48
+            | generation << 51
+            | USED;
+
+        let bucket = self.bucket(key);
+        let slot = bucket.iter()
+            .find(|slot| {
+                let data = slot.data.load(Ordering::Relaxed);
+                data & USED != 0 && slot.key.load(Ordering::Relaxed) ^ data == key
+            })
+            .unwrap_or_else(|| {
+                bucket.iter()
+                    .min_by_key(|slot| {
+                        let data = slot.data.load(Ordering::Relaxed);
+                        if data & USED == 0 {
+                            return i32::MIN;
+                        }
+                        let age = (generation.wrapping_sub((data >> 51) & 0xFF) & 0xFF) as i32;
+                        ((data >> 32) & 0xFF) as i32 - 8 * age
+                    })
+                    .unwrap()
+            });
+
+        slot.key.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    // Permille of the table holding entries written during this move
+    pub fn hashfull(&self) -> u32 {
+        let generation = self.generation();
+        let sample = HASHFULL_SAMPLE.min(self.slots.len());
+
+        let current = self.slots[..sample].iter()
+            .filter(|slot| {
+                let data = slot.data.load(Ordering::Relaxed);
+                data & USED != 0 && (data >> 51) & 0xFF == generation
+            })
+            .count();
+
+        (current * 1000 / sample) as u32
+    }
+}
+
+fn pack_direction(direction: Direction) -> u64 {
+    match direction {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
+        Direction::None => 4,
+    }
+}
+
+fn unpack_direction(bits: u64) -> Direction {
+    match bits & 0b111 {
+        0 => Direction::Up,
+        1 => Direction::Down,
+        2 => Direction::Left,
+        3 => Direction::Right,
+        _ => Direction::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECTIONS: [Direction; 5] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right, Direction::None];
+
+    fn entry(depth: i32, score: i32) -> TTEntry {
+        TTEntry { best_move: Direction::Up, second_best_move: Direction::Left, worst_move: Direction::Down, depth, score, flag: EXACT }
+    }
+
+    // A key that lands in the same bucket as `key`, `n` of them apart
+    fn same_bucket(table: &TranspositionTable, key: u64, n: u64) -> u64 {
+        key + n * (table.mask as u64 + 1)
+    }
+
+    #[test]
+    fn entries_come_back_as_they_went_in() {
+        let table = TranspositionTable::new(1);
+        let scores = [0, 1, -1, 1234, -1234, 100000 - 7, -100000 + 7, i32::MAX, i32::MIN];
+        let mut key = 0x1234_5678_9ABC_DEF0_u64;
+
+        for &flag in &[UPPER, LOWER, EXACT] {
+            for &depth in &[0, 1, 37, 64, 255] {
+                for &score in &scores {
+                    for (i, &best_move) in DIRECTIONS.iter().enumerate() {
+                        let stored = TTEntry {
+                            best_move,
+                            second_best_move: DIRECTIONS[(i + 1) % 5],
+                            worst_move: DIRECTIONS[(i + 2) % 5],
+                            depth,
+                            score,
+                            flag,
+                        };
+                        key = key.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(1);
+                        table.store(key, stored);
+                        assert_eq!(table.probe(key), Some(stored));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn depths_out_of_range_are_clamped() {
+        let table = TranspositionTable::new(1);
+
+        table.store(1, entry(300, 5));
+        assert_eq!(table.probe(1).map(|entry| entry.depth), Some(255));
+        table.store(2, entry(-3, 5));
+        assert_eq!(table.probe(2).map(|entry| entry.depth), Some(0));
+    }
+
+    #[test]
+    fn other_keys_in_the_bucket_miss() {
+        let table = TranspositionTable::new(1);
+        table.store(42, entry(5, 10));
+
+        assert_eq!(table.probe(same_bucket(&table, 42, 1)), None);
+        assert_eq!(table.probe(42 ^ (1 << 63)), None);
+    }
+
+    #[test]
+    fn torn_entries_miss() {
+        let table = TranspositionTable::new(1);
+        table.store(42, entry(5, 10));
+        table.store(same_bucket(&table, 42, 1), entry(9, -20));
+
+        // Another thread got as far as the data of a different position but not its key
+        let slot = table.bucket(42).iter().find(|slot| slot.data.load(Ordering::Relaxed) & USED != 0).unwrap();
+        let other = table.bucket(42).iter().rev().find(|slot| slot.data.load(Ordering::Relaxed) & USED != 0).unwrap();
+        slot.data.store(other.data.load(Ordering::Relaxed), Ordering::Relaxed);
+
+        assert_eq!(table.probe(42), None);
+    }
+
+    #[test]
+    fn storing_a_position_again_overwrites_it() {
+        let table = TranspositionTable::new(1);
+        for n in 0..BUCKET_SIZE as u64 {
+            table.store(same_bucket(&table, 7, n), entry(10, n as i32));
+        }
+
+        table.store(7, entry(2, 99));
+
+        assert_eq!(table.probe(7), Some(entry(2, 99)));
+        for n in 1..BUCKET_SIZE as u64 {
+            assert_eq!(table.probe(same_bucket(&table, 7, n)), Some(entry(10, n as i32)));
+        }
+    }
+
+    #[test]
+    fn the_shallowest_entry_goes_first() {
+        let table = TranspositionTable::new(1);
+        let depths = [6, 3, 9, 12];
+        for (n, depth) in depths.iter().enumerate() {
+            table.store(same_bucket(&table, 7, n as u64), entry(*depth, 0));
+        }
+
+        table.store(same_bucket(&table, 7, 4), entry(1, 0));
+
+        assert_eq!(table.probe(same_bucket(&table, 7, 1)), None);
+        for n in [0, 2, 3, 4] {
+            assert!(table.probe(same_bucket(&table, 7, n)).is_some());
+        }
+    }
+
+    #[test]
+    fn earlier_moves_go_before_deeper_searches() {
+        let table = TranspositionTable::new(1);
+        table.new_search();
+        for n in 0..BUCKET_SIZE as u64 {
+            table.store(same_bucket(&table, 7, n), entry(5 + n as i32, 0));
+        }
+
+        // A move later, a shallow entry of this move is kept over deeper ones of the last
+        table.new_search();
+        table.store(same_bucket(&table, 7, 4), entry(1, 0));
+        assert_eq!(table.probe(7), None);
+
+        table.store(same_bucket(&table, 7, 5), entry(1, 0));
+        assert!(table.probe(same_bucket(&table, 7, 4)).is_some());
+        assert_eq!(table.probe(same_bucket(&table, 7, 1)), None);
+    }
+}
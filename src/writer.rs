@@ -0,0 +1,62 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use log::error;
+
+// Hands whatever it's sent to a thread of its own, so nothing sending it ever waits on the disk.
+// Dropping it waits for everything sent so far to be written.
+pub struct BackgroundWriter<T> {
+    // None if writing is off or the thread couldn't be started
+    sender: Option<Sender<T>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> BackgroundWriter<T> {
+    pub fn off() -> Self {
+        Self { sender: None, thread: None }
+    }
+
+    // `write` runs on a thread called `name` for everything sent, in order
+    pub fn start(name: &str, mut write: impl FnMut(T) + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel::<T>();
+
+        let spawned = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                for item in receiver {
+                    write(item);
+                }
+            });
+
+        match spawned {
+            Ok(thread) => Self { sender: Some(sender), thread: Some(thread) },
+            Err(e) => {
+                error!("Couldn't start the {} writer, nothing will be written: {}", name, e);
+                Self::off()
+            }
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    // False if the thread is gone and `item` with it
+    pub fn send(&self, item: T) -> bool {
+        match &self.sender {
+            Some(sender) => sender.send(item).is_ok(),
+            None => true,
+        }
+    }
+}
+
+impl<T> Drop for BackgroundWriter<T> {
+    fn drop(&mut self) {
+        // The thread runs out of things to write once the sender is gone
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("A writer thread panicked, some of what it was sent wasn't written");
+            }
+        }
+    }
+}
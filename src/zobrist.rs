@@ -0,0 +1,74 @@
+use crate::bitboard::MAX_CELLS;
+use crate::board::MAX_SNAKES;
+
+// Health runs from 0 to 100
+const HEALTHS: usize = 101;
+// A body can't be longer than the board plus the couple of segments stacked on its tail
+const LENGTHS: usize = MAX_CELLS + 9;
+
+// What a snake's body segment is keyed as. Every segment gets a `Body` key, the first and the
+// last one get a `Head` and a `Tail` key on top, so moving a snake only touches its ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Body,
+    Head,
+    Tail,
+}
+
+// Keys for everything that tells one position from another: food, hazards, every snake's own
+// segments, health and length, and whose turn it is. They are worked out at compile time for
+// the largest board there is, so every game and every run hashes a position the same way.
+static FOOD: [u64; MAX_CELLS] = keys(1);
+// (snake, cell, segment)
+static SEGMENTS: [u64; MAX_SNAKES * MAX_CELLS * 3] = keys(2);
+// (snake, health)
+static HEALTH: [u64; MAX_SNAKES * HEALTHS] = keys(3);
+// (snake, length), lengths past the table share its last key
+static LENGTH: [u64; MAX_SNAKES * LENGTHS] = keys(4);
+// (snake to move)
+static TURN: [u64; MAX_SNAKES] = keys(5);
+// Hazards move in royale, and the eval charges for them, so a table entry from before a
+// shrink mustn't be found after it
+static HAZARD: [u64; MAX_CELLS] = keys(6);
+
+// SplitMix64 from `seed`, every table gets its own
+const fn keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut state = seed.wrapping_mul(0xD1B5_4A32_D192_ED03);
+    let mut i = 0;
+
+    while i < N {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+
+    keys
+}
+
+pub fn food(cell: usize) -> u64 {
+    FOOD[cell]
+}
+
+pub fn hazard(cell: usize) -> u64 {
+    HAZARD[cell]
+}
+
+pub fn segment(snake: usize, cell: usize, segment: Segment) -> u64 {
+    SEGMENTS[(snake * MAX_CELLS + cell) * 3 + segment as usize]
+}
+
+pub fn health(snake: usize, health: i32) -> u64 {
+    HEALTH[snake * HEALTHS + health.clamp(0, HEALTHS as i32 - 1) as usize]
+}
+
+pub fn length(snake: usize, length: i32) -> u64 {
+    LENGTH[snake * LENGTHS + (length.max(0) as usize).min(LENGTHS - 1)]
+}
+
+pub fn turn(snake: usize) -> u64 {
+    TURN[snake]
+}